@@ -0,0 +1,125 @@
+use crate::transport::Transport;
+use crate::{An, Config, F, IntoStereo, Process, Track};
+
+/// Timeline of named sections in bars that loops after its last section.
+#[derive(Clone)]
+pub struct Arrangement {
+    timeline: Vec<(&'static str, u32)>,
+    entry: Option<usize>,
+    section: &'static str,
+    bars: u32,
+    queued: Option<&'static str>,
+    bar: Option<u64>,
+}
+
+impl Arrangement {
+    pub fn new(timeline: impl IntoIterator<Item = (&'static str, u32)>) -> Self {
+        let timeline = timeline.into_iter().collect::<Vec<_>>();
+        assert!(
+            timeline.iter().all(|(_, bars)| *bars > 0),
+            "sections are at least one bar long"
+        );
+        Self {
+            entry: (!timeline.is_empty()).then_some(0),
+            section: timeline.first().map_or("", |(section, _)| section),
+            timeline,
+            bars: 0,
+            queued: None,
+            bar: None,
+        }
+    }
+
+    /// Keep the position unless the timeline changed.
+    pub fn retain(&mut self, old: &Arrangement) {
+        if self.timeline == old.timeline {
+            self.clone_from(old);
+        }
+    }
+
+    pub fn section(&self) -> &'static str {
+        self.section
+    }
+
+    /// Switch to `section` at the start of the next bar.
+    pub fn queue(&mut self, section: &'static str) {
+        self.queued = Some(section);
+    }
+
+    fn jump(&mut self, section: &'static str) {
+        self.entry = self.timeline.iter().position(|(name, _)| *name == section);
+        self.section = section;
+        self.bars = 0;
+    }
+
+    pub fn update(&mut self, transport: &mut Transport) {
+        let bar = *self.bar.get_or_insert(transport.bar());
+        if transport.bar() != bar {
+            self.bar = Some(transport.bar());
+            self.bars += 1;
+            if let Some(section) = self.queued.take() {
+                self.jump(section);
+            } else if let Some(entry) = self.entry
+                && self.bars >= self.timeline[entry].1
+            {
+                let next = (entry + 1) % self.timeline.len();
+                self.jump(self.timeline[next].0);
+                self.entry = Some(next);
+            }
+        }
+        transport.section = self.section;
+    }
+}
+
+/// Track that plays the graph of the current section of the `song`, reset when
+/// the section starts.
+pub fn sections() -> An<Sections> {
+    An(Sections {
+        graphs: Vec::new(),
+        current: None,
+        section: None,
+    })
+}
+
+pub struct Sections {
+    graphs: Vec<(&'static str, Track)>,
+    current: Option<usize>,
+    section: Option<&'static str>,
+}
+
+impl An<Sections> {
+    pub fn section<P>(mut self, section: &'static str, graph: P) -> Self
+    where
+        P: Process<Input = ()> + 'static,
+        P::Output: IntoStereo + 'static,
+    {
+        self.0.graphs.push((section, crate::track(graph)));
+        self
+    }
+}
+
+impl Process for Sections {
+    type Input = ();
+    type Output = F<2>;
+
+    fn reset(&mut self) {
+        for (_, graph) in self.graphs.iter_mut() {
+            graph.reset();
+        }
+        self.section = None;
+    }
+
+    fn sample(&mut self, config: &Config, _: Self::Input) -> Self::Output {
+        let section = config.transport.section;
+        if self.section != Some(section) {
+            self.section = Some(section);
+            self.current = self.graphs.iter().position(|(name, _)| *name == section);
+            if let Some(current) = self.current {
+                self.graphs[current].1.reset();
+            }
+        }
+        match self.current {
+            Some(current) => self.graphs[current].1.sample(config, ()),
+            None => F([0.0; 2]),
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use crate::fft::{Complex, Fft};
+use crate::wav::Wav;
+use crate::{An, Config, F, IntoStereo, MonoSrcBound, Process};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Block size of each partition, which is also the latency of the wet signal.
+const BLOCK: usize = 128;
+
+/// Convolve the input with a mono or stereo impulse response, resampled to
+/// the rate of the audio device.
+pub fn convolve<In>(ir: impl Into<Arc<Wav>>) -> An<Convolve<In, f32>>
+where
+    In: IntoStereo,
+{
+    let ir = ir.into();
+    let sample_rate = crate::device_rate().unwrap_or(ir.sample_rate) as f64;
+    An(Convolve {
+        engines: prepare(&ir, sample_rate, In::channels() == 1),
+        ir,
+        sample_rate,
+        pending: None,
+        mix: 0.3,
+        _in: PhantomData,
+    })
+}
+
+pub struct Convolve<In, Mix> {
+    ir: Arc<Wav>,
+    engines: Vec<Partitioned>,
+    sample_rate: f64,
+    pending: Option<Pending>,
+    mix: Mix,
+    _in: PhantomData<In>,
+}
+
+/// Engines being prepared for a new sample rate on another thread.
+struct Pending {
+    sample_rate: f64,
+    engines: Receiver<Vec<Partitioned>>,
+    old: Sender<Vec<Partitioned>>,
+}
+
+fn prepare(ir: &Wav, sample_rate: f64, mono: bool) -> Vec<Partitioned> {
+    let ir = ir.resample(sample_rate as u32);
+    let energy = ir
+        .channels
+        .iter()
+        .map(|channel| channel.iter().map(|s| s * s).sum::<f32>())
+        .fold(0.0, f32::max);
+    let scale = if energy > 0.0 {
+        energy.sqrt().recip()
+    } else {
+        0.0
+    };
+
+    let engines = if mono && ir.channels.len() == 1 { 1 } else { 2 };
+    (0..engines)
+        .map(|i| {
+            let channel = &ir.channels[i.min(ir.channels.len() - 1)];
+            Partitioned::new(channel, scale)
+        })
+        .collect()
+}
+
+impl<In, Mix> Convolve<In, Mix>
+where
+    In: IntoStereo,
+{
+    fn follow(&mut self, sample_rate: f64) {
+        match &self.pending {
+            Some(pending) if pending.sample_rate == sample_rate => {
+                if let Ok(engines) = pending.engines.try_recv() {
+                    let _ = pending
+                        .old
+                        .send(std::mem::replace(&mut self.engines, engines));
+                    self.sample_rate = sample_rate;
+                    self.pending = None;
+                }
+            }
+            _ => {
+                let (sender, receiver) = mpsc::channel();
+                let (old_sender, old) = mpsc::channel::<Vec<Partitioned>>();
+                let ir = self.ir.clone();
+                std::thread::spawn(move || {
+                    let _ = sender.send(prepare(&ir, sample_rate, In::channels() == 1));
+                    drop(old.recv());
+                });
+                self.pending = Some(Pending {
+                    sample_rate,
+                    engines: receiver,
+                    old: old_sender,
+                });
+            }
+        }
+    }
+}
+
+impl<In, Mix> An<Convolve<In, Mix>>
+where
+    In: IntoStereo,
+    Mix: MonoSrcBound,
+{
+    pub fn mix<Mix1>(self, mix: Mix1) -> An<Convolve<In, Mix1>>
+    where
+        Mix1: MonoSrcBound,
+    {
+        An(Convolve {
+            ir: self.0.ir,
+            engines: self.0.engines,
+            sample_rate: self.0.sample_rate,
+            pending: self.0.pending,
+            mix,
+            _in: PhantomData,
+        })
+    }
+}
+
+impl<In, Mix> Process for Convolve<In, Mix>
+where
+    In: IntoStereo,
+    Mix: MonoSrcBound,
+{
+    type Input = In;
+    type Output = F<2>;
+
+    fn reset(&mut self) {
+        for engine in self.engines.iter_mut() {
+            engine.reset();
+        }
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let mix = self.mix.filter_mono(config);
+        if self.sample_rate != config.sample_rate {
+            self.follow(config.sample_rate);
+        }
+
+        let input = input.into_stereo();
+        let wet = match &mut self.engines[..] {
+            [mono] => [mono.sample(input[0]); 2],
+            [left, right] => [left.sample(input[0]), right.sample(input[1])],
+            _ => unreachable!(),
+        };
+        F([
+            input[0] * (1.0 - mix) + wet[0] * mix,
+            input[1] * (1.0 - mix) + wet[1] * mix,
+        ])
+    }
+}
+
+/// Uniformly partitioned overlap-save convolution that spreads the work of
+/// long impulse responses across each block.
+///
+/// See Wefers, F. (2015). Partitioned convolution algorithms for real-time
+/// auralization.
+struct Partitioned {
+    fft: Fft,
+    partitions: Vec<Vec<Complex>>,
+    /// Spectra of the most recent input blocks, newest at `head`.
+    history: Vec<Vec<Complex>>,
+    head: usize,
+    input: Vec<f32>,
+    output: Vec<f32>,
+    accumulator: Vec<Complex>,
+    position: usize,
+    partition: usize,
+    per_sample: usize,
+}
+
+impl Partitioned {
+    fn new(ir: &[f32], scale: f32) -> Self {
+        let fft = Fft::new(BLOCK * 2);
+        let partitions = ir
+            .chunks(BLOCK)
+            .map(|chunk| {
+                let mut spectrum = vec![Complex::default(); BLOCK * 2];
+                for (bin, sample) in spectrum.iter_mut().zip(chunk) {
+                    bin.re = sample * scale;
+                }
+                fft.forward(&mut spectrum);
+                spectrum
+            })
+            .collect::<Vec<_>>();
+        let len = partitions.len().max(1);
+        Self {
+            fft,
+            history: vec![vec![Complex::default(); BLOCK * 2]; len],
+            partitions,
+            head: 0,
+            input: vec![0.0; BLOCK * 2],
+            output: vec![0.0; BLOCK],
+            accumulator: vec![Complex::default(); BLOCK * 2],
+            position: 0,
+            partition: 1,
+            per_sample: (len - 1).div_ceil(BLOCK),
+        }
+    }
+
+    fn reset(&mut self) {
+        for spectrum in self.history.iter_mut() {
+            spectrum.fill(Complex::default());
+        }
+        self.input.fill(0.0);
+        self.output.fill(0.0);
+        self.accumulator.fill(Complex::default());
+        self.position = 0;
+        self.partition = 1;
+    }
+
+    fn sample(&mut self, input: f32) -> f32 {
+        let output = self.output[self.position];
+        self.input[BLOCK + self.position] = input;
+
+        let end = (self.partition + self.per_sample).min(self.partitions.len());
+        for p in self.partition..end {
+            let history =
+                &self.history[(self.head + self.history.len() - (p - 1)) % self.history.len()];
+            for ((acc, x), h) in self
+                .accumulator
+                .iter_mut()
+                .zip(history)
+                .zip(&self.partitions[p])
+            {
+                *acc = *acc + *x * *h;
+            }
+        }
+        self.partition = end.max(self.partition);
+
+        self.position += 1;
+        if self.position == BLOCK {
+            self.block();
+        }
+        output
+    }
+
+    fn block(&mut self) {
+        self.head = (self.head + 1) % self.history.len();
+        let spectrum = &mut self.history[self.head];
+        for (bin, sample) in spectrum.iter_mut().zip(&self.input) {
+            *bin = Complex::new(*sample, 0.0);
+        }
+        self.fft.forward(spectrum);
+
+        if let Some(first) = self.partitions.first() {
+            for ((acc, x), h) in self.accumulator.iter_mut().zip(spectrum.iter()).zip(first) {
+                *acc = *acc + *x * *h;
+            }
+        }
+        self.fft.inverse(&mut self.accumulator);
+        for (output, bin) in self.output.iter_mut().zip(&self.accumulator[BLOCK..]) {
+            *output = bin.re;
+        }
+
+        self.accumulator.fill(Complex::default());
+        self.input.copy_within(BLOCK.., 0);
+        self.position = 0;
+        self.partition = 1;
+    }
+}
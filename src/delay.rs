@@ -0,0 +1,413 @@
+use crate::filter::OnePole;
+use crate::{An, Config, F, IntoStereo, MonoSrcBound, Process, TimeUnit, fmono, math};
+use std::marker::PhantomData;
+
+pub fn delay<Time>(seconds: Time) -> An<Delay<Time, f32, f32, f32>>
+where
+    Time: MonoSrcBound,
+{
+    An(Delay::new(TimeUnit::Seconds, seconds))
+}
+
+/// Delay `Time` beats, locked to the tempo.
+pub fn delay_beats<Time>(beats: Time) -> An<Delay<Time, f32, f32, f32>>
+where
+    Time: MonoSrcBound,
+{
+    An(Delay::new(TimeUnit::Beats, beats))
+}
+
+pub struct Delay<Time, Feedback, Damp, Mix> {
+    line: DelayLine,
+    lp: OnePole,
+    unit: TimeUnit,
+    time: Time,
+    feedback: Feedback,
+    damp: Damp,
+    mix: Mix,
+}
+
+impl<Time> Delay<Time, f32, f32, f32> {
+    pub fn new(unit: TimeUnit, time: Time) -> Self {
+        Self {
+            line: DelayLine::default(),
+            lp: OnePole::default(),
+            unit,
+            time,
+            feedback: 0.5,
+            damp: 8_000.0,
+            mix: 0.5,
+        }
+    }
+}
+
+impl<Time, Feedback, Damp, Mix> An<Delay<Time, Feedback, Damp, Mix>>
+where
+    Time: MonoSrcBound,
+    Feedback: MonoSrcBound,
+    Damp: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    pub fn feedback<Feedback1>(self, feedback: Feedback1) -> An<Delay<Time, Feedback1, Damp, Mix>>
+    where
+        Feedback1: MonoSrcBound,
+    {
+        An(Delay {
+            line: self.0.line,
+            lp: self.0.lp,
+            unit: self.0.unit,
+            time: self.0.time,
+            feedback,
+            damp: self.0.damp,
+            mix: self.0.mix,
+        })
+    }
+
+    /// Cutoff in Hz of the lowpass inside the feedback loop.
+    pub fn damp<Damp1>(self, damp: Damp1) -> An<Delay<Time, Feedback, Damp1, Mix>>
+    where
+        Damp1: MonoSrcBound,
+    {
+        An(Delay {
+            line: self.0.line,
+            lp: self.0.lp,
+            unit: self.0.unit,
+            time: self.0.time,
+            feedback: self.0.feedback,
+            damp,
+            mix: self.0.mix,
+        })
+    }
+
+    pub fn mix<Mix1>(self, mix: Mix1) -> An<Delay<Time, Feedback, Damp, Mix1>>
+    where
+        Mix1: MonoSrcBound,
+    {
+        An(Delay {
+            line: self.0.line,
+            lp: self.0.lp,
+            unit: self.0.unit,
+            time: self.0.time,
+            feedback: self.0.feedback,
+            damp: self.0.damp,
+            mix,
+        })
+    }
+}
+
+impl<Time, Feedback, Damp, Mix> Process for Delay<Time, Feedback, Damp, Mix>
+where
+    Time: MonoSrcBound,
+    Feedback: MonoSrcBound,
+    Damp: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    type Input = F<1>;
+    type Output = F<1>;
+
+    fn reset(&mut self) {
+        self.line.clear();
+        self.lp.reset();
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let time = self.time.filter_mono(config) as f64;
+        let feedback = self.feedback.filter_mono(config);
+        let damp = self.damp.filter_mono(config) as f64;
+        let mix = self.mix.filter_mono(config);
+
+        let delay = self.unit.samples(time, config);
+        self.line.reserve(delay);
+        let wet = self.line.tap(delay);
+        let coefficient = OnePole::coefficient(damp, config.sample_rate);
+        let fb = self.lp.filter(wet, coefficient) * feedback;
+        self.line.write(input[0] + fb);
+        fmono(input[0] * (1.0 - mix) + wet * mix)
+    }
+}
+
+/// Stereo delay whose repeats alternate between left and right.
+pub fn pingpong<In, Time>(seconds: Time) -> An<PingPong<In, Time, f32, f32, f32>>
+where
+    In: IntoStereo,
+    Time: MonoSrcBound,
+{
+    An(PingPong::new(TimeUnit::Seconds, seconds))
+}
+
+pub fn pingpong_beats<In, Time>(beats: Time) -> An<PingPong<In, Time, f32, f32, f32>>
+where
+    In: IntoStereo,
+    Time: MonoSrcBound,
+{
+    An(PingPong::new(TimeUnit::Beats, beats))
+}
+
+pub struct PingPong<In, Time, Feedback, Damp, Mix> {
+    lines: [DelayLine; 2],
+    lp: [OnePole; 2],
+    unit: TimeUnit,
+    time: Time,
+    feedback: Feedback,
+    damp: Damp,
+    mix: Mix,
+    _in: PhantomData<In>,
+}
+
+impl<In, Time> PingPong<In, Time, f32, f32, f32> {
+    pub fn new(unit: TimeUnit, time: Time) -> Self {
+        Self {
+            lines: Default::default(),
+            lp: Default::default(),
+            unit,
+            time,
+            feedback: 0.5,
+            damp: 8_000.0,
+            mix: 0.5,
+            _in: PhantomData,
+        }
+    }
+}
+
+impl<In, Time, Feedback, Damp, Mix> An<PingPong<In, Time, Feedback, Damp, Mix>>
+where
+    In: IntoStereo,
+    Time: MonoSrcBound,
+    Feedback: MonoSrcBound,
+    Damp: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    pub fn feedback<Feedback1>(
+        self,
+        feedback: Feedback1,
+    ) -> An<PingPong<In, Time, Feedback1, Damp, Mix>>
+    where
+        Feedback1: MonoSrcBound,
+    {
+        An(PingPong {
+            lines: self.0.lines,
+            lp: self.0.lp,
+            unit: self.0.unit,
+            time: self.0.time,
+            feedback,
+            damp: self.0.damp,
+            mix: self.0.mix,
+            _in: PhantomData,
+        })
+    }
+
+    pub fn damp<Damp1>(self, damp: Damp1) -> An<PingPong<In, Time, Feedback, Damp1, Mix>>
+    where
+        Damp1: MonoSrcBound,
+    {
+        An(PingPong {
+            lines: self.0.lines,
+            lp: self.0.lp,
+            unit: self.0.unit,
+            time: self.0.time,
+            feedback: self.0.feedback,
+            damp,
+            mix: self.0.mix,
+            _in: PhantomData,
+        })
+    }
+
+    pub fn mix<Mix1>(self, mix: Mix1) -> An<PingPong<In, Time, Feedback, Damp, Mix1>>
+    where
+        Mix1: MonoSrcBound,
+    {
+        An(PingPong {
+            lines: self.0.lines,
+            lp: self.0.lp,
+            unit: self.0.unit,
+            time: self.0.time,
+            feedback: self.0.feedback,
+            damp: self.0.damp,
+            mix,
+            _in: PhantomData,
+        })
+    }
+}
+
+impl<In, Time, Feedback, Damp, Mix> Process for PingPong<In, Time, Feedback, Damp, Mix>
+where
+    In: IntoStereo,
+    Time: MonoSrcBound,
+    Feedback: MonoSrcBound,
+    Damp: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    type Input = In;
+    type Output = F<2>;
+
+    fn reset(&mut self) {
+        for (line, lp) in self.lines.iter_mut().zip(self.lp.iter_mut()) {
+            line.clear();
+            lp.reset();
+        }
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let time = self.time.filter_mono(config) as f64;
+        let feedback = self.feedback.filter_mono(config);
+        let damp = self.damp.filter_mono(config) as f64;
+        let mix = self.mix.filter_mono(config);
+
+        let delay = self.unit.samples(time, config);
+        let coefficient = OnePole::coefficient(damp, config.sample_rate);
+        let [left, right] = &mut self.lines;
+        left.reserve(delay);
+        right.reserve(delay);
+        let wet = [left.tap(delay), right.tap(delay)];
+        let input = input.into_stereo();
+        let mono = (input[0] + input[1]) * 0.5;
+        left.write(mono + self.lp[1].filter(wet[1], coefficient) * feedback);
+        right.write(self.lp[0].filter(wet[0], coefficient) * feedback);
+        F([
+            input[0] * (1.0 - mix) + wet[0] * mix,
+            input[1] * (1.0 - mix) + wet[1] * mix,
+        ])
+    }
+}
+
+pub fn multitap<In>() -> An<MultiTap<In>>
+where
+    In: IntoStereo,
+{
+    An(MultiTap::new(TimeUnit::Seconds))
+}
+
+pub fn multitap_beats<In>() -> An<MultiTap<In>>
+where
+    In: IntoStereo,
+{
+    An(MultiTap::new(TimeUnit::Beats))
+}
+
+pub struct MultiTap<In> {
+    line: DelayLine,
+    unit: TimeUnit,
+    taps: Vec<Tap>,
+    dry: f32,
+    _in: PhantomData<In>,
+}
+
+#[derive(Clone, Copy)]
+struct Tap {
+    time: f64,
+    left: f32,
+    right: f32,
+}
+
+impl<In> MultiTap<In> {
+    pub fn new(unit: TimeUnit) -> Self {
+        Self {
+            line: DelayLine::default(),
+            unit,
+            taps: Vec::new(),
+            dry: 1.0,
+            _in: PhantomData,
+        }
+    }
+}
+
+impl<In> An<MultiTap<In>>
+where
+    In: IntoStereo,
+{
+    /// Add a tap `time` after the input, panned from -1 to 1.
+    pub fn tap(mut self, time: f32, gain: f32, pan: f32) -> Self {
+        let (left, right) = math::pan(pan);
+        self.0.taps.push(Tap {
+            time: time as f64,
+            left: left * gain,
+            right: right * gain,
+        });
+        self
+    }
+
+    pub fn dry(mut self, gain: f32) -> Self {
+        self.0.dry = gain;
+        self
+    }
+}
+
+impl<In> Process for MultiTap<In>
+where
+    In: IntoStereo,
+{
+    type Input = In;
+    type Output = F<2>;
+
+    fn reset(&mut self) {
+        self.line.clear();
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let input = input.into_stereo();
+        let mut output = F([input[0] * self.dry, input[1] * self.dry]);
+        for tap in self.taps.iter() {
+            let delay = self.unit.samples(tap.time, config);
+            self.line.reserve(delay);
+            let wet = self.line.tap(delay);
+            output[0] += wet * tap.left;
+            output[1] += wet * tap.right;
+        }
+        self.line.write((input[0] + input[1]) * 0.5);
+        output
+    }
+}
+
+/// Circular buffer with fractional reads that grows to fit the longest delay.
+#[derive(Default)]
+pub struct DelayLine {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl DelayLine {
+    pub fn new(samples: usize) -> Self {
+        let mut line = Self::default();
+        line.reserve(samples as f64);
+        line
+    }
+
+    pub fn reserve(&mut self, samples: f64) {
+        let len = (samples.max(0.0) as usize + 4).next_power_of_two();
+        if len <= self.buffer.len() {
+            return;
+        }
+
+        let mut buffer = vec![0.0; len];
+        let old_mask = self.buffer.len().wrapping_sub(1);
+        let new_mask = len - 1;
+        for i in 0..self.buffer.len() {
+            let index = self.index.wrapping_sub(i + 1);
+            buffer[index & new_mask] = self.buffer[index & old_mask];
+        }
+        self.index &= new_mask;
+        self.buffer = buffer;
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.fill(0.0);
+    }
+
+    pub fn write(&mut self, sample: f32) {
+        let mask = self.buffer.len() - 1;
+        self.buffer[self.index & mask] = sample;
+        self.index = (self.index + 1) & mask;
+    }
+
+    /// Read the sample written `delay` writes ago, where 1 is the last one.
+    pub fn tap(&self, delay: f64) -> f32 {
+        let mask = self.buffer.len() - 1;
+        let delay = delay.clamp(1.0, (self.buffer.len() - 3) as f64);
+        let whole = delay as usize;
+        let t = (delay - whole as f64) as f32;
+        let at = |d: usize| self.buffer[self.index.wrapping_sub(d) & mask];
+        let x1 = at(whole);
+        let x0 = if whole > 1 { at(whole - 1) } else { x1 };
+        math::hermite(t, x0, x1, at(whole + 1), at(whole + 2))
+    }
+}
@@ -0,0 +1,274 @@
+use crate::delay::DelayLine;
+use crate::filter::{BiquadParams, BiquadState};
+use crate::oversample::{self, Downsampler, MAX_FACTOR, Upsampler};
+use crate::rng::{Rng, rand};
+use crate::{An, Config, F, MonoSrcBound, Process, fmono};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    Tanh,
+    /// Cubic soft clip that reaches full scale at an input of 1.
+    SoftClip,
+    /// Compresses the negative half harder, adding even harmonics.
+    Tube,
+    /// Folds the signal back on itself whenever it exceeds full scale.
+    Foldback,
+    HardClip,
+}
+
+impl Curve {
+    pub fn shape(self, x: f32) -> f32 {
+        match self {
+            Self::Tanh => x.tanh(),
+            Self::SoftClip => {
+                let x = x.clamp(-1.0, 1.0);
+                1.5 * x - 0.5 * x * x * x
+            }
+            Self::Tube => {
+                if x >= 0.0 {
+                    x.tanh()
+                } else {
+                    x.exp() - 1.0
+                }
+            }
+            Self::Foldback => ((x - 1.0).rem_euclid(4.0) - 2.0).abs() - 1.0,
+            Self::HardClip => x.clamp(-1.0, 1.0),
+        }
+    }
+}
+
+/// Waveshaping distortion with 2x oversampling.
+pub fn shaper(curve: Curve) -> An<Shaper<f32, f32, f32>> {
+    An(Shaper {
+        curve,
+        up: Upsampler::new(2),
+        down: Downsampler::new(2),
+        dry: DelayLine::new(oversample::latency(2) as usize),
+        drive: 1.0,
+        bias: 0.0,
+        mix: 1.0,
+    })
+}
+
+pub struct Shaper<Drive, Bias, Mix> {
+    curve: Curve,
+    up: Upsampler,
+    down: Downsampler,
+    /// Delays the dry signal to line up with the oversampled wet signal.
+    dry: DelayLine,
+    drive: Drive,
+    bias: Bias,
+    mix: Mix,
+}
+
+impl<Drive, Bias, Mix> An<Shaper<Drive, Bias, Mix>>
+where
+    Drive: MonoSrcBound,
+    Bias: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    pub fn oversampling(mut self, factor: usize) -> Self {
+        assert!(
+            factor <= MAX_FACTOR / 2,
+            "shaper oversampling is limited to 8x"
+        );
+        self.0.up = Upsampler::new(factor);
+        self.0.down = Downsampler::new(factor);
+        self.0.dry = DelayLine::new(oversample::latency(factor) as usize);
+        self
+    }
+
+    pub fn drive<Drive1>(self, drive: Drive1) -> An<Shaper<Drive1, Bias, Mix>>
+    where
+        Drive1: MonoSrcBound,
+    {
+        An(Shaper {
+            curve: self.0.curve,
+            up: self.0.up,
+            down: self.0.down,
+            dry: self.0.dry,
+            drive,
+            bias: self.0.bias,
+            mix: self.0.mix,
+        })
+    }
+
+    pub fn bias<Bias1>(self, bias: Bias1) -> An<Shaper<Drive, Bias1, Mix>>
+    where
+        Bias1: MonoSrcBound,
+    {
+        An(Shaper {
+            curve: self.0.curve,
+            up: self.0.up,
+            down: self.0.down,
+            dry: self.0.dry,
+            drive: self.0.drive,
+            bias,
+            mix: self.0.mix,
+        })
+    }
+
+    pub fn mix<Mix1>(self, mix: Mix1) -> An<Shaper<Drive, Bias, Mix1>>
+    where
+        Mix1: MonoSrcBound,
+    {
+        An(Shaper {
+            curve: self.0.curve,
+            up: self.0.up,
+            down: self.0.down,
+            dry: self.0.dry,
+            drive: self.0.drive,
+            bias: self.0.bias,
+            mix,
+        })
+    }
+}
+
+impl<Drive, Bias, Mix> Process for Shaper<Drive, Bias, Mix>
+where
+    Drive: MonoSrcBound,
+    Bias: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    type Input = F<1>;
+    type Output = F<1>;
+
+    fn reset(&mut self) {
+        self.up.reset();
+        self.down.reset();
+        self.dry.clear();
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let drive = self.drive.filter_mono(config);
+        let bias = self.bias.filter_mono(config);
+        let mix = self.mix.filter_mono(config);
+
+        let factor = self.up.factor();
+        let mut buffer = [0.0; MAX_FACTOR];
+        let buffer = &mut buffer[..factor];
+        self.up.process(input[0], buffer);
+        let offset = self.curve.shape(bias);
+        for sample in buffer.iter_mut() {
+            *sample = self.curve.shape(*sample * drive + bias) - offset;
+        }
+        let wet = self.down.process(buffer);
+
+        let latency = oversample::latency(factor);
+        let dry = if latency > 0.0 {
+            let dry = self.dry.tap(latency);
+            self.dry.write(input[0]);
+            dry
+        } else {
+            input[0]
+        };
+        fmono(dry * (1.0 - mix) + wet * mix)
+    }
+}
+
+/// Bit depth and sample rate reduction, where `bits` may be fractional.
+pub fn crush<Bits>(bits: Bits) -> An<Crush<Bits, f32>>
+where
+    Bits: MonoSrcBound,
+{
+    An(Crush {
+        bits,
+        rate: f32::INFINITY,
+        antialias: None,
+        dither: None,
+        phase: 1.0,
+        held: 0.0,
+    })
+}
+
+pub struct Crush<Bits, Rate> {
+    bits: Bits,
+    rate: Rate,
+    antialias: Option<(BiquadParams, BiquadState, f64)>,
+    dither: Option<An<Rng>>,
+    phase: f64,
+    held: f32,
+}
+
+impl<Bits, Rate> An<Crush<Bits, Rate>>
+where
+    Bits: MonoSrcBound,
+    Rate: MonoSrcBound,
+{
+    /// Sample and hold at `rate` Hz.
+    pub fn rate<Rate1>(self, rate: Rate1) -> An<Crush<Bits, Rate1>>
+    where
+        Rate1: MonoSrcBound,
+    {
+        An(Crush {
+            bits: self.0.bits,
+            rate,
+            antialias: self.0.antialias,
+            dither: self.0.dither,
+            phase: self.0.phase,
+            held: self.0.held,
+        })
+    }
+
+    pub fn antialias(mut self) -> Self {
+        self.0.antialias = Some((BiquadParams::default(), BiquadState::default(), 0.0));
+        self
+    }
+
+    pub fn dither(mut self, seed: u32) -> Self {
+        self.0.dither = Some(rand(seed));
+        self
+    }
+}
+
+impl<Bits, Rate> Process for Crush<Bits, Rate>
+where
+    Bits: MonoSrcBound,
+    Rate: MonoSrcBound,
+{
+    type Input = F<1>;
+    type Output = F<1>;
+
+    fn reset(&mut self) {
+        if let Some((_, state, _)) = &mut self.antialias {
+            *state = BiquadState::default();
+        }
+        if let Some(dither) = &mut self.dither {
+            dither.reset();
+        }
+        self.phase = 1.0;
+        self.held = 0.0;
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let bits = self.bits.filter_mono(config).max(1.0);
+        let rate = (self.rate.filter_mono(config) as f64).min(config.sample_rate);
+
+        let mut sample = input[0];
+        if let Some((params, state, cutoff)) = &mut self.antialias {
+            let nyquist = rate * 0.45;
+            if *cutoff != nyquist {
+                *params = BiquadParams::lpf(
+                    nyquist,
+                    core::f64::consts::FRAC_1_SQRT_2,
+                    config.sample_rate,
+                );
+                *cutoff = nyquist;
+            }
+            sample = state.filter(params, sample as f64) as f32;
+        }
+
+        self.phase += rate * config.sample_duration;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.held = sample;
+        }
+
+        let step = 2.0 / 2f32.powf(bits);
+        let noise = match &mut self.dither {
+            Some(rng) => (rng.sample(config, ())[0] - rng.sample(config, ())[0]) * step,
+            None => 0.0,
+        };
+        fmono(((self.held + noise) / step).round() * step)
+    }
+}
@@ -0,0 +1,242 @@
+use crate::{An, Config, F, MonoSrcBound, Process, TimeUnit, fmono};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EnvCurve {
+    Linear,
+    /// Fast at first and slowing towards the target, like an analog envelope.
+    Exp,
+}
+
+impl EnvCurve {
+    pub fn shape(self, t: f64) -> f64 {
+        match self {
+            Self::Linear => t,
+            Self::Exp => (1.0 - (-5.0 * t).exp()) / (1.0 - (-5f64).exp()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// Attack, decay, sustain, release envelope, held open while the gate is above
+/// 0 and scaled by the gate level at the start of a note.
+pub fn adsr<Attack, Decay, Sustain, Release>(
+    attack: Attack,
+    decay: Decay,
+    sustain: Sustain,
+    release: Release,
+) -> An<Adsr<Attack, Decay, Sustain, Release>>
+where
+    Attack: MonoSrcBound,
+    Decay: MonoSrcBound,
+    Sustain: MonoSrcBound,
+    Release: MonoSrcBound,
+{
+    An(Adsr {
+        attack,
+        decay,
+        sustain,
+        release,
+        curve: EnvCurve::Linear,
+        sensitivity: 1.0,
+        gate: false,
+        stage: Stage::Idle,
+        t: 0.0,
+        from: 0.0,
+        level: 0.0,
+        velocity: 1.0,
+    })
+}
+
+pub struct Adsr<Attack, Decay, Sustain, Release> {
+    attack: Attack,
+    decay: Decay,
+    sustain: Sustain,
+    release: Release,
+    curve: EnvCurve,
+    sensitivity: f32,
+    gate: bool,
+    stage: Stage,
+    t: f64,
+    from: f32,
+    level: f32,
+    velocity: f32,
+}
+
+impl<Attack, Decay, Sustain, Release> An<Adsr<Attack, Decay, Sustain, Release>>
+where
+    Attack: MonoSrcBound,
+    Decay: MonoSrcBound,
+    Sustain: MonoSrcBound,
+    Release: MonoSrcBound,
+{
+    pub fn curve(mut self, curve: EnvCurve) -> Self {
+        self.0.curve = curve;
+        self
+    }
+
+    /// How much the gate level scales the output, from 0 to 1.
+    pub fn velocity(mut self, sensitivity: f32) -> Self {
+        self.0.sensitivity = sensitivity.clamp(0.0, 1.0);
+        self
+    }
+}
+
+impl<Attack, Decay, Sustain, Release> Process for Adsr<Attack, Decay, Sustain, Release>
+where
+    Attack: MonoSrcBound,
+    Decay: MonoSrcBound,
+    Sustain: MonoSrcBound,
+    Release: MonoSrcBound,
+{
+    type Input = F<1>;
+    type Output = F<1>;
+
+    fn reset(&mut self) {
+        self.gate = false;
+        self.stage = Stage::Idle;
+        self.t = 0.0;
+        self.from = 0.0;
+        self.level = 0.0;
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let attack = self.attack.filter_mono(config) as f64;
+        let decay = self.decay.filter_mono(config) as f64;
+        let sustain = self.sustain.filter_mono(config).clamp(0.0, 1.0);
+        let release = self.release.filter_mono(config) as f64;
+
+        let gate = input[0] > 0.0;
+        if gate && !self.gate {
+            self.stage = Stage::Attack;
+            self.t = 0.0;
+            self.from = self.level;
+            self.velocity = 1.0 - self.sensitivity + self.sensitivity * input[0];
+        } else if !gate && self.gate {
+            self.stage = Stage::Release;
+            self.t = 0.0;
+            self.from = self.level;
+        }
+        self.gate = gate;
+
+        let (to, time) = match self.stage {
+            Stage::Idle => return fmono(0.0),
+            Stage::Attack => (1.0, attack),
+            Stage::Decay => (sustain, decay),
+            Stage::Sustain => {
+                self.level = sustain;
+                return fmono(self.level * self.velocity);
+            }
+            Stage::Release => (0.0, release),
+        };
+
+        self.t = if time > 0.0 {
+            (self.t + config.sample_duration / time).min(1.0)
+        } else {
+            1.0
+        };
+        self.level = self.from + (to - self.from) * self.curve.shape(self.t) as f32;
+        if self.t >= 1.0 {
+            self.t = 0.0;
+            self.from = to;
+            self.stage = match self.stage {
+                Stage::Attack => Stage::Decay,
+                Stage::Decay => Stage::Sustain,
+                _ => Stage::Idle,
+            };
+        }
+        fmono(self.level * self.velocity)
+    }
+}
+
+/// Envelope that ramps from 0 to the level of each `(duration, level)` point
+/// in turn and holds the last level.
+pub fn breakpoints(points: impl IntoIterator<Item = (f32, f32)>) -> An<Breakpoints> {
+    An(Breakpoints {
+        points: points.into_iter().collect(),
+        unit: TimeUnit::Seconds,
+        curve: EnvCurve::Linear,
+        looping: None,
+        segment: 0,
+        t: 0.0,
+        from: 0.0,
+        level: 0.0,
+    })
+}
+
+#[derive(Clone)]
+pub struct Breakpoints {
+    points: Vec<(f32, f32)>,
+    unit: TimeUnit,
+    curve: EnvCurve,
+    looping: Option<(usize, usize)>,
+    segment: usize,
+    t: f64,
+    from: f32,
+    level: f32,
+}
+
+impl An<Breakpoints> {
+    pub fn beats(mut self) -> Self {
+        self.0.unit = TimeUnit::Beats;
+        self
+    }
+
+    pub fn curve(mut self, curve: EnvCurve) -> Self {
+        self.0.curve = curve;
+        self
+    }
+
+    /// Jump back to point `start` after reaching point `end`, until reset.
+    pub fn looping(mut self, start: usize, end: usize) -> Self {
+        assert!(
+            start <= end && end < self.0.points.len(),
+            "loop points out of bounds for `breakpoints`"
+        );
+        self.0.looping = Some((start, end));
+        self
+    }
+}
+
+impl Process for Breakpoints {
+    type Input = ();
+    type Output = F<1>;
+
+    fn reset(&mut self) {
+        self.segment = 0;
+        self.t = 0.0;
+        self.from = 0.0;
+        self.level = 0.0;
+    }
+
+    fn sample(&mut self, config: &Config, _: Self::Input) -> Self::Output {
+        let Some(&(duration, to)) = self.points.get(self.segment) else {
+            return fmono(self.level);
+        };
+        let output = self.level;
+
+        let samples = self.unit.samples(duration as f64, config);
+        self.t = if samples > 1.0 {
+            (self.t + 1.0 / samples).min(1.0)
+        } else {
+            1.0
+        };
+        self.level = self.from + (to - self.from) * self.curve.shape(self.t) as f32;
+        if self.t >= 1.0 {
+            self.t = 0.0;
+            self.from = to;
+            self.segment = match self.looping {
+                Some((start, end)) if self.segment == end => start,
+                _ => self.segment + 1,
+            };
+        }
+        fmono(output)
+    }
+}
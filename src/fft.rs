@@ -0,0 +1,113 @@
+#[derive(Default, Clone, Copy)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+}
+
+impl core::ops::Add for Complex {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl core::ops::Sub for Complex {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl core::ops::Mul for Complex {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+/// In-place radix-2 FFT that doesn't allocate after [`Fft::new`].
+pub struct Fft {
+    twiddles: Vec<Complex>,
+    reverse: Vec<usize>,
+}
+
+impl Fft {
+    pub fn new(len: usize) -> Self {
+        assert!(len.is_power_of_two(), "FFT size must be a power of two");
+        let bits = len.trailing_zeros();
+        let twiddles = (0..len / 2)
+            .map(|i| {
+                let (sin, cos) = (-core::f64::consts::TAU * i as f64 / len as f64).sin_cos();
+                Complex::new(cos as f32, sin as f32)
+            })
+            .collect();
+        let reverse = (0..len)
+            .map(|i| {
+                i.reverse_bits()
+                    .checked_shr(usize::BITS - bits)
+                    .unwrap_or(0)
+            })
+            .collect();
+        Self { twiddles, reverse }
+    }
+
+    pub fn len(&self) -> usize {
+        self.reverse.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reverse.is_empty()
+    }
+
+    pub fn forward(&self, buffer: &mut [Complex]) {
+        self.transform(buffer, false);
+    }
+
+    /// Inverse transform, scaled by `1 / len`.
+    pub fn inverse(&self, buffer: &mut [Complex]) {
+        self.transform(buffer, true);
+        let scale = 1.0 / self.len() as f32;
+        for x in buffer.iter_mut() {
+            x.re *= scale;
+            x.im *= scale;
+        }
+    }
+
+    fn transform(&self, buffer: &mut [Complex], inverse: bool) {
+        let len = self.len();
+        debug_assert_eq!(buffer.len(), len);
+        for (i, &j) in self.reverse.iter().enumerate() {
+            if i < j {
+                buffer.swap(i, j);
+            }
+        }
+
+        let mut size = 2;
+        while size <= len {
+            let half = size / 2;
+            let stride = len / size;
+            for start in (0..len).step_by(size) {
+                for k in 0..half {
+                    let mut w = self.twiddles[k * stride];
+                    if inverse {
+                        w.im = -w.im;
+                    }
+                    let a = buffer[start + k];
+                    let b = buffer[start + k + half] * w;
+                    buffer[start + k] = a + b;
+                    buffer[start + k + half] = a - b;
+                }
+            }
+            size *= 2;
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{An, Config, F, MonoSrcBound, Process, fmono};
+use crate::{An, Config, F, MonoSrcBound, Process, fmono, math};
 
 pub fn fadein<const CHANNELS: usize>(duration: f32) -> An<FadeIn<CHANNELS>> {
     An(FadeIn {
@@ -48,10 +48,7 @@ where
 
 pub struct Biquad<Freq, Q, Env, Depth> {
     params: BiquadParams,
-    x1: f64,
-    x2: f64,
-    y1: f64,
-    y2: f64,
+    state: BiquadState,
     freq: Freq,
     q: Q,
     env: Env,
@@ -63,10 +60,7 @@ impl<Freq, Q, Env, Depth> Biquad<Freq, Q, Env, Depth> {
     pub fn new(params: BiquadParams, freq: Freq, q: Q, env: Env, depth: Depth) -> Self {
         Biquad {
             params,
-            x1: 0.0,
-            x2: 0.0,
-            y1: 0.0,
-            y2: 0.0,
+            state: BiquadState::default(),
             freq,
             q,
             env,
@@ -89,10 +83,7 @@ where
     {
         An(Biquad {
             params: self.0.params,
-            x1: self.0.x1,
-            x2: self.0.x2,
-            y1: self.0.y1,
-            y2: self.0.y2,
+            state: self.0.state,
             freq: self.0.freq,
             q,
             env: self.0.env,
@@ -107,10 +98,7 @@ where
     {
         An(Biquad {
             params: self.0.params,
-            x1: self.0.x1,
-            x2: self.0.x2,
-            y1: self.0.y1,
-            y2: self.0.y2,
+            state: self.0.state,
             freq: self.0.freq,
             q: self.0.q,
             env,
@@ -125,10 +113,7 @@ where
     {
         An(Biquad {
             params: self.0.params,
-            x1: self.0.x1,
-            x2: self.0.x2,
-            y1: self.0.y1,
-            y2: self.0.y2,
+            state: self.0.state,
             freq: self.0.freq,
             q: self.0.q,
             env: self.0.env,
@@ -160,22 +145,14 @@ where
             self.mfreq = mfreq;
         }
 
-        let x0 = input[0] as f64;
-        let y0 = self.params.b0 * x0 + self.params.b1 * self.x1 + self.params.b2 * self.x2
-            - self.params.a1 * self.y1
-            - self.params.a2 * self.y2;
-        self.x2 = self.x1;
-        self.x1 = x0;
-        self.y2 = self.y1;
-        self.y1 = y0;
-        fmono(y0 as f32)
+        fmono(self.state.filter(&self.params, input[0] as f64) as f32)
     }
 }
 
 /// Implementation based on these resources:
 /// - https://github.com/SamiPerttu/fundsp/blob/master/src/biquad.rs
 /// - https://webaudio.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html
-#[derive(Default)]
+#[derive(Default, Clone, Copy)]
 pub struct BiquadParams {
     a1: f64,
     a2: f64,
@@ -200,6 +177,27 @@ impl BiquadParams {
     }
 }
 
+#[derive(Default, Clone, Copy)]
+pub struct BiquadState {
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+impl BiquadState {
+    pub fn filter(&mut self, params: &BiquadParams, x0: f64) -> f64 {
+        let y0 = params.b0 * x0 + params.b1 * self.x1 + params.b2 * self.x2
+            - params.a1 * self.y1
+            - params.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x0;
+        self.y2 = self.y1;
+        self.y1 = y0;
+        y0
+    }
+}
+
 pub struct Limiter {
     follower: Follower,
     buffer: Vec<f32>,
@@ -230,6 +228,41 @@ impl Limiter {
     }
 }
 
+/// Lookahead limiter and hard clip, the default master chain of `acid!`.
+pub fn limiter() -> An<MasterLimiter> {
+    An(MasterLimiter {
+        limiters: Vec::new(),
+        sample_rate: 0.0,
+    })
+}
+
+pub struct MasterLimiter {
+    limiters: Vec<Limiter>,
+    sample_rate: f64,
+}
+
+impl Process for MasterLimiter {
+    type Input = F<2>;
+    type Output = F<2>;
+
+    fn reset(&mut self) {
+        self.limiters.clear();
+    }
+
+    fn sample(&mut self, config: &Config, mut input: Self::Input) -> Self::Output {
+        if self.limiters.is_empty() || self.sample_rate != config.sample_rate {
+            self.sample_rate = config.sample_rate;
+            self.limiters = (0..2)
+                .map(|_| Limiter::new(crate::ms(1.0), crate::ms(500.0), config.sample_rate))
+                .collect();
+        }
+        for (sample, limiter) in input.iter_mut().zip(self.limiters.iter_mut()) {
+            *sample = math::clamp(limiter.limit(*sample));
+        }
+        input
+    }
+}
+
 struct Follower {
     env: f64,
     att: f64,
@@ -255,3 +288,48 @@ impl Follower {
         self.env as f32
     }
 }
+
+#[derive(Default, Clone, Copy)]
+pub struct OnePole {
+    y: f32,
+}
+
+impl OnePole {
+    pub fn coefficient(cutoff_hz: f64, sample_rate: f64) -> f32 {
+        (1.0 - (-core::f64::consts::TAU * cutoff_hz / sample_rate).exp()) as f32
+    }
+
+    pub fn reset(&mut self) {
+        self.y = 0.0;
+    }
+
+    pub fn filter(&mut self, sample: f32, coefficient: f32) -> f32 {
+        self.y += (sample - self.y) * coefficient;
+        self.y
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct Allpass {
+    x1: f32,
+    y1: f32,
+}
+
+impl Allpass {
+    /// Coefficient that shifts the phase by 90 degrees at `freq_hz`.
+    pub fn coefficient(freq_hz: f64, sample_rate: f64) -> f32 {
+        let t = (core::f64::consts::PI * freq_hz / sample_rate).tan();
+        ((t - 1.0) / (t + 1.0)) as f32
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn filter(&mut self, sample: f32, coefficient: f32) -> f32 {
+        let y = coefficient * sample + self.x1 - coefficient * self.y1;
+        self.x1 = sample;
+        self.y1 = y;
+        y
+    }
+}
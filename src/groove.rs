@@ -0,0 +1,112 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+pub const MAX_STEPS: usize = 32;
+
+/// Per-step timing offsets, as fractions of a step, and velocities that accent
+/// [`trigs`](crate::trig::trigs). The groove repeats from the start of the
+/// transport, so sequences sharing one swing together.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Groove {
+    len: usize,
+    offsets: [f32; MAX_STEPS],
+    velocities: [f32; MAX_STEPS],
+}
+
+impl Groove {
+    /// Missing velocities default to 1.
+    pub fn new(offsets: &[f32], velocities: &[f32]) -> Self {
+        assert!(
+            (1..=MAX_STEPS).contains(&offsets.len()) && velocities.len() <= offsets.len(),
+            "a groove has between 1 and {MAX_STEPS} steps"
+        );
+        let mut groove = Self {
+            len: offsets.len(),
+            offsets: [0.0; MAX_STEPS],
+            velocities: [1.0; MAX_STEPS],
+        };
+        for (offset, value) in groove.offsets.iter_mut().zip(offsets) {
+            *offset = value.clamp(-0.5, 0.5);
+        }
+        groove.velocities[..velocities.len()].copy_from_slice(velocities);
+        groove
+    }
+
+    /// MPC-style swing, where 50 is straight and 66 is close to triplets.
+    pub fn swing(percent: f32) -> Self {
+        let offset = (percent / 100.0).clamp(0.5, 0.75) * 2.0 - 1.0;
+        Self::new(&[0.0, offset], &[])
+    }
+
+    /// One step per line of an offset and an optional velocity, with `#`
+    /// comments.
+    pub fn parse(text: &str) -> Result<Self> {
+        let invalid = |line: usize, message: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("groove line {}: {message}", line + 1),
+            )
+        };
+        let mut offsets = Vec::new();
+        let mut velocities = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut values = line.split_whitespace().map(|value| value.parse::<f32>());
+            let offset = values
+                .next()
+                .and_then(|offset| offset.ok())
+                .ok_or_else(|| invalid(i, "expected an offset"))?;
+            let velocity = match values.next() {
+                Some(velocity) => velocity.map_err(|_| invalid(i, "expected a velocity"))?,
+                None => 1.0,
+            };
+            if values.next().is_some() {
+                return Err(invalid(i, "expected at most two values"));
+            }
+            offsets.push(offset);
+            velocities.push(velocity);
+        }
+        if !(1..=MAX_STEPS).contains(&offsets.len()) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("a groove has between 1 and {MAX_STEPS} steps"),
+            ));
+        }
+        Ok(Self::new(&offsets, &velocities))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// The same groove without velocities.
+    pub fn timing(mut self) -> Self {
+        self.velocities = [1.0; MAX_STEPS];
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn offset(&self, step: i64) -> f64 {
+        self.offsets[step.rem_euclid(self.len as i64) as usize] as f64
+    }
+
+    pub fn velocity(&self, step: i64) -> f32 {
+        self.velocities[step.rem_euclid(self.len as i64) as usize]
+    }
+}
+
+impl Default for Groove {
+    fn default() -> Self {
+        Self::new(&[0.0], &[])
+    }
+}
@@ -2,22 +2,57 @@
 #![feature(trait_alias)]
 #![feature(generic_const_exprs)]
 
+pub mod arrange;
+pub mod convolve;
+pub mod delay;
+pub mod distort;
+pub mod envelope;
+pub mod fft;
 pub mod filter;
+pub mod groove;
 pub mod math;
+pub mod mixer;
+pub mod modulation;
 pub mod note;
 pub mod osc;
+pub mod oversample;
+pub mod pattern;
+pub mod reverb;
 pub mod rng;
+pub mod sampler;
+pub mod stereo;
+pub mod transport;
+pub mod trig;
+pub mod wav;
 
 pub mod prelude {
+    pub use super::arrange::sections;
+    pub use super::convolve::*;
+    pub use super::delay::*;
+    pub use super::distort::*;
+    pub use super::envelope::*;
     pub use super::filter::*;
+    pub use super::groove::Groove;
+    pub use super::mixer::Strip;
+    pub use super::modulation::*;
     pub use super::note::*;
     pub use super::osc::*;
+    pub use super::oversample::OversampleExt;
+    pub use super::pattern::*;
+    pub use super::reverb::*;
     pub use super::rng::*;
+    pub use super::sampler::*;
+    pub use super::stereo::*;
+    pub use super::transport::Dur;
+    pub use super::trig::*;
+    pub use super::wav;
     pub use super::{An, MonoSrcBound, Process, ResetExt, env, expdecay, fmono, samples};
 }
 
 use crate::math::IntoSignal;
+use crate::transport::Transport;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU32, Ordering};
 
 crate::acid!(
     bpm: 140.0,
@@ -60,72 +95,316 @@ macro_rules! mix {
         }
     }
 }
+/// Tracks with optional mixer strips and sends in dB, send/return buses, a
+/// master chain that defaults to a limiter, and an optional time signature and
+/// song of sections in bars.
+///
+/// ```ignore
+/// acid!(
+///     bpm: 140.0,
+///     signature: 7 / 8,
+///     song: [intro: 8, drop: 16],
+///     kick: kick909(),
+///     bass: bass() => { gain: -6.0 } send { verb: -12.0 },
+///     verb: bus(reverb().mix(1)),
+///     master: width(1.2) >> limiter(),
+/// );
+/// ```
 #[macro_export]
 macro_rules! acid {
     {
         bpm: $bpm:expr,
-        $($name:ident: $sound:expr),* $(,)?
+        signature: $beats:literal / $unit:literal,
+        $($rest:tt)*
     } => {
+        $crate::acid!(@song [$bpm, ($beats, $unit)] $($rest)*);
+    };
+    {
+        bpm: $bpm:expr,
+        $($rest:tt)*
+    } => {
+        $crate::acid!(@song [$bpm, (4, 4)] $($rest)*);
+    };
+    (
+        @song [$($header:tt)*]
+        song: [$($section:ident: $bars:expr),* $(,)?],
+        $($rest:tt)*
+    ) => {
+        $crate::acid!(
+            @parse header: [$($header)*, [$((stringify!($section), $bars)),*]],
+            master: [],
+            tracks: [],
+            buses: [],
+            $($rest)*
+        );
+    };
+    (@song [$($header:tt)*] $($rest:tt)*) => {
+        $crate::acid!(@parse header: [$($header)*, []], master: [], tracks: [], buses: [], $($rest)*);
+    };
+    (
+        @parse header: $header:tt, master: [], tracks: [$($tracks:tt)*], buses: [$($buses:tt)*],
+        master: $master:expr
+        $(, $($rest:tt)*)?
+    ) => {
+        $crate::acid!(
+            @parse header: $header,
+            master: [$master],
+            tracks: [$($tracks)*],
+            buses: [$($buses)*],
+            $($($rest)*)?
+        );
+    };
+    (
+        @parse header: $header:tt, master: [$($master:tt)+], tracks: [$($tracks:tt)*], buses: [$($buses:tt)*],
+        master: $($rest:tt)*
+    ) => {
+        compile_error!("`master` is declared more than once");
+    };
+    (
+        @parse header: $header:tt, master: [$($master:tt)*], tracks: [$($tracks:tt)*], buses: [$($buses:tt)*],
+        $name:ident: bus($effect:expr)
+        $(=> { $($field:ident: $value:expr),* $(,)? })?
+        $(, $($rest:tt)*)?
+    ) => {
+        $crate::acid!(
+            @parse header: $header,
+            master: [$($master)*],
+            tracks: [$($tracks)*],
+            buses: [$($buses)* ($name, $effect, [$($($field: $value,)*)?])],
+            $($($rest)*)?
+        );
+    };
+    (
+        @parse header: $header:tt, master: [$($master:tt)*], tracks: [$($tracks:tt)*], buses: [$($buses:tt)*],
+        $name:ident: $sound:expr
+        $(=>
+            $({ $($field:ident: $value:expr),* $(,)? })?
+            $(send { $($send:ident: $level:expr),* $(,)? })?
+        )?
+        $(, $($rest:tt)*)?
+    ) => {
+        $crate::acid!(
+            @parse header: $header,
+            master: [$($master)*],
+            tracks: [
+                $($tracks)*
+                (
+                    $name,
+                    $sound,
+                    [$($($($field: $value,)*)?)?],
+                    [$($($($send: $level,)*)?)?]
+                )
+            ],
+            buses: [$($buses)*],
+            $($($rest)*)?
+        );
+    };
+    (@master) => {
+        $crate::filter::limiter()
+    };
+    (@master $master:expr) => {
+        $master
+    };
+    (
+        @parse header: [$bpm:expr, ($beats:expr, $unit:expr), [$($song:expr),*]],
+        master: [$($master:expr)?],
+        tracks: [$((
+            $name:ident,
+            $sound:expr,
+            [$($field:ident: $value:expr,)*],
+            [$($send:ident: $level:expr,)*]
+        ))*],
+        buses: [$((
+            $bus:ident,
+            $effect:expr,
+            [$($bus_field:ident: $bus_value:expr,)*]
+        ))*],
+    ) => {
         // need to send to the audio thread in `glazer::audio_stub`
         unsafe impl Send for Acid {}
         pub struct Acid {
-            limiters: Vec<$crate::prelude::Limiter>,
-            sounds: Vec<(Box<dyn $crate::Process<Input = (), Output = $crate::F<1>>>, Vec<f32>)>,
+            transport: $crate::transport::Transport,
+            sample_rate: u32,
+            tempo: $crate::transport::Tempo,
+            arrangement: $crate::arrange::Arrangement,
+            master: $crate::mixer::Bus,
+            sounds: Vec<$crate::Track>,
+            buses: Vec<$crate::mixer::Bus>,
+            channels: Vec<$crate::mixer::Channel>,
+            returns: Vec<$crate::mixer::Channel>,
         }
         impl Default for Acid {
             fn default() -> Self {
                 Self {
-                    limiters: Vec::new(),
+                    transport: $crate::transport::Transport::new(
+                        $crate::transport::TimeSignature::new($beats, $unit),
+                    ),
+                    sample_rate: 0,
+                    tempo: $crate::transport::Tempo::new($bpm),
+                    arrangement: $crate::arrange::Arrangement::new([$($song),*]),
+                    master: build_master(),
                     sounds: build_sounds(),
+                    buses: build_buses(),
+                    channels: build_channels(),
+                    returns: build_returns(),
                 }
             }
         }
         impl Acid {
             pub fn rebuild_sounds(&mut self) {
+                // statics start over in a reloaded library
+                $crate::set_device_rate(self.sample_rate);
+                let mut tempo = $crate::transport::Tempo::new($bpm);
+                tempo.retain(&self.tempo);
+                self.tempo = tempo;
+                let mut arrangement = $crate::arrange::Arrangement::new([$($song),*]);
+                arrangement.retain(&self.arrangement);
+                self.arrangement = arrangement;
+                self.master = build_master();
                 self.sounds = build_sounds();
+                self.buses = build_buses();
+                for (channels, old) in [
+                    (build_channels(), &mut self.channels),
+                    (build_returns(), &mut self.returns),
+                ] {
+                    let mut channels = channels;
+                    for channel in channels.iter_mut() {
+                        if let Some(old) = old.iter().find(|old| old.name == channel.name) {
+                            channel.retain(old);
+                        }
+                    }
+                    *old = channels;
+                }
+            }
+            pub fn transport(&self) -> &$crate::transport::Transport {
+                &self.transport
+            }
+            pub fn tempo(&mut self) -> &mut $crate::transport::Tempo {
+                &mut self.tempo
+            }
+            pub fn queue(&mut self, section: &'static str) {
+                self.arrangement.queue(section);
+            }
+            pub fn signature(&mut self, beats: u32, unit: u32) {
+                self.transport
+                    .set_signature($crate::transport::TimeSignature::new(beats, unit));
+            }
+            pub fn strip(&mut self, name: &str) -> Option<&mut $crate::mixer::Strip> {
+                match $crate::mixer::strip(&mut self.channels, name) {
+                    Some(strip) => Some(strip),
+                    None => $crate::mixer::strip(&mut self.returns, name),
+                }
+            }
+            /// Space taps the tempo, `1` to `9` toggle mute and `q` to `o` toggle solo of
+            /// the tracks in order.
+            pub fn key(&mut self, key: char) {
+                let (index, solo) = match key {
+                    ' ' => return self.tempo.tap(::std::time::Instant::now()),
+                    '1'..='9' => (key as usize - '1' as usize, false),
+                    _ => match "qwertyuio".find(key) {
+                        Some(index) => (index, true),
+                        None => return,
+                    },
+                };
+                if let Some(channel) = self.channels.get_mut(index) {
+                    if solo {
+                        channel.strip.solo ^= true;
+                    } else {
+                        channel.strip.mute ^= true;
+                    }
+                }
+            }
+            pub fn send(&mut self, track: &str, bus: &str) -> Option<&mut f32> {
+                $crate::mixer::send(&mut self.channels, &self.returns, track, bus)
             }
             pub fn process(&mut self, samples: &mut [f32], sample_rate: u32, channels: usize) {
-                let [$($name),*] = &mut self.sounds[..] else { unreachable!() };
-                let len = samples.len();
-                $(if $name.1.len() < len { $name.1.extend((0..len).map(|_| 0.0)) })*
-                let config = $crate::Config {
+                if self.sample_rate != sample_rate {
+                    self.sample_rate = sample_rate;
+                    $crate::set_device_rate(sample_rate);
+                }
+                let mut config = $crate::Config {
                     sample_rate: sample_rate as f64,
                     sample_duration: 1.0 / sample_rate as f64,
                     channels,
-                    bpm: $bpm,
-                    spb: (60.0 / ($bpm)) * sample_rate as f64,
-                    bps: 1.0 / ((60.0 / ($bpm)) * sample_rate as f64),
+                    bpm: 0.0,
+                    spb: 0.0,
+                    bps: 0.0,
+                    transport: self.transport,
+                    step: None,
                 };
-                $(
-                    let buf = &mut $name.1[0..len];
-                    for frame in buf.chunks_mut(channels) {
-                        let sample = $name.0.sample(&config, ());
-                        frame.fill(sample.0[0]);
-                    }
-                )*
-                while self.limiters.len() < channels {
-                    self.limiters.push(
-                        $crate::prelude::Limiter::new(
-                            $crate::ms(1.0),
-                            $crate::ms(500.0),
-                            sample_rate as f64,
-                        )
-                    );
-                }
-                $crate::mix!(samples, $($name.1),*);
+                let solo = self.channels.iter().any(|channel| channel.strip.solo);
                 for frame in samples.chunks_mut(channels) {
-                    for (sample, limiter) in frame.iter_mut().zip(self.limiters.iter_mut()) {
-                        *sample = $crate::math::clamp(limiter.limit(*sample));
+                    config.set_bpm(self.tempo.update(&config.transport));
+                    self.arrangement.update(&mut config.transport);
+                    let mut mix = $crate::F([0.0; 2]);
+                    let mut sends = [$crate::F([0.0; 2]); BUS_COUNT];
+                    for (sound, channel) in self.sounds.iter_mut().zip(self.channels.iter_mut()) {
+                        // muted tracks keep running so they come back in time
+                        let frame = sound.sample(&config, ());
+                        mix = mix + channel.process(&config, frame, solo, &mut sends);
+                    }
+                    for ((effect, channel), send) in
+                        self.buses.iter_mut().zip(self.returns.iter_mut()).zip(sends)
+                    {
+                        // returns are not silenced by soloing a track
+                        let frame = effect.sample(&config, send);
+                        mix = mix + channel.process(&config, frame, false, &mut []);
                     }
+                    $crate::write_frame(frame, self.master.sample(&config, mix));
+                    // protect the output from a master chain that overshoots
+                    for sample in frame.iter_mut() {
+                        *sample = $crate::math::clamp(*sample);
+                    }
+                    config.transport.advance(config.spb);
                 }
+                self.transport = config.transport;
             }
         }
-        fn build_sounds() -> Vec<(Box<dyn $crate::Process<Input = (), Output = $crate::F<1>>>, Vec<f32>)> {
-            vec![$((Box::new($sound), Vec::new())),*]
+        #[allow(dead_code, non_camel_case_types)]
+        enum Buses {
+            $($bus),*
+        }
+        const BUS_COUNT: usize = <[&str]>::len(&[$(stringify!($bus)),*]);
+        fn build_master() -> $crate::mixer::Bus {
+            $crate::mixer::bus($crate::acid!(@master $($master)?))
+        }
+        fn build_sounds() -> Vec<$crate::Track> {
+            vec![$($crate::track($sound)),*]
+        }
+        fn build_buses() -> Vec<$crate::mixer::Bus> {
+            vec![$($crate::mixer::bus($effect)),*]
+        }
+        fn build_channels() -> Vec<$crate::mixer::Channel> {
+            vec![$({
+                #[allow(unused_mut)]
+                let mut sends = vec![f32::NEG_INFINITY; BUS_COUNT];
+                $(sends[Buses::$send as usize] = $level;)*
+                $crate::mixer::Channel::new(
+                    stringify!($name),
+                    $crate::mixer::Strip {
+                        $($field: $value,)*
+                        ..Default::default()
+                    },
+                    sends,
+                )
+            }),*]
+        }
+        fn build_returns() -> Vec<$crate::mixer::Channel> {
+            vec![$(
+                $crate::mixer::Channel::new(
+                    stringify!($bus),
+                    $crate::mixer::Strip {
+                        $($bus_field: $bus_value,)*
+                        ..Default::default()
+                    },
+                    Vec::new(),
+                )
+            ),*]
         }
     };
 }
 
+#[derive(Clone)]
 pub struct Config {
     pub sample_rate: f64,
     pub sample_duration: f64,
@@ -133,12 +412,71 @@ pub struct Config {
     pub bpm: f64,
     pub spb: f64,
     pub bps: f64,
+    pub transport: Transport,
+    /// Step of the enclosing `.seg()` while it samples its source, counted from
+    /// its last reset.
+    pub step: Option<i64>,
+}
+
+static DEVICE_RATE: AtomicU32 = AtomicU32::new(0);
+
+/// Sample rate of the audio device once `acid!` has processed a block.
+pub fn device_rate() -> Option<u32> {
+    Some(DEVICE_RATE.load(Ordering::Relaxed)).filter(|rate| *rate > 0)
+}
+
+pub fn set_device_rate(sample_rate: u32) {
+    DEVICE_RATE.store(sample_rate, Ordering::Relaxed);
+}
+
+impl Config {
+    pub fn set_bpm(&mut self, bpm: f64) {
+        self.bpm = bpm;
+        self.spb = 60.0 / bpm * self.sample_rate;
+        self.bps = 1.0 / self.spb;
+    }
+}
+
+pub type Track = Box<dyn Process<Input = (), Output = F<2>>>;
+
+pub fn track<P>(sound: P) -> Track
+where
+    P: Process<Input = ()> + 'static,
+    P::Output: IntoStereo + 'static,
+{
+    Box::new(An(sound) >> process(|_, frame: P::Output| frame.into_stereo()))
+}
+
+/// Write a stereo frame into an interleaved frame of any channel count.
+pub fn write_frame(frame: &mut [f32], stereo: F<2>) {
+    if let [mono] = frame {
+        *mono = (stereo[0] + stereo[1]) * 0.5;
+    } else {
+        for (i, sample) in frame.iter_mut().enumerate() {
+            *sample = stereo[i % 2];
+        }
+    }
 }
 
 pub fn ms(ms: f32) -> f32 {
     ms / 1_000.0
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Seconds,
+    Beats,
+}
+
+impl TimeUnit {
+    pub fn samples(self, value: f64, config: &Config) -> f64 {
+        match self {
+            Self::Seconds => value * config.sample_rate,
+            Self::Beats => value * config.spb,
+        }
+    }
+}
+
 pub fn fmono(s: f32) -> F<1> {
     F([s])
 }
@@ -181,6 +519,7 @@ pub trait Frame: Default + Copy {
     fn new() -> Self;
     fn channels() -> usize;
     fn as_slice(&self) -> &[f32];
+    fn as_mut_slice(&mut self) -> &mut [f32];
 }
 impl Frame for () {
     fn new() -> Self {}
@@ -190,6 +529,9 @@ impl Frame for () {
     fn as_slice(&self) -> &[f32] {
         &[]
     }
+    fn as_mut_slice(&mut self) -> &mut [f32] {
+        &mut []
+    }
 }
 impl<const CHANNELS: usize> Frame for F<CHANNELS> {
     fn new() -> Self {
@@ -201,6 +543,22 @@ impl<const CHANNELS: usize> Frame for F<CHANNELS> {
     fn as_slice(&self) -> &[f32] {
         &self.0
     }
+    fn as_mut_slice(&mut self) -> &mut [f32] {
+        &mut self.0
+    }
+}
+pub trait IntoStereo: Frame {
+    fn into_stereo(self) -> F<2>;
+}
+impl IntoStereo for F<1> {
+    fn into_stereo(self) -> F<2> {
+        F([self.0[0]; 2])
+    }
+}
+impl IntoStereo for F<2> {
+    fn into_stereo(self) -> F<2> {
+        self
+    }
 }
 pub trait Process {
     type Input: Frame;
@@ -212,6 +570,11 @@ pub trait Process {
         debug_assert_eq!(Self::Output::channels(), 1);
         self.sample(config, Self::Input::new()).as_slice()[0]
     }
+    /// Whether the output is a gate, which `.seg()` drops on the last sample of
+    /// each step so back-to-back hits retrigger.
+    fn is_gate(&self) -> bool {
+        false
+    }
 }
 pub trait MonoSrcBound = Process<Input = (), Output = F<1>>;
 impl<T> Process for T
@@ -332,6 +695,9 @@ where
     fn sample(&mut self, config: &crate::Config, input: Self::Input) -> Self::Output {
         self.0.sample(config, input)
     }
+    fn is_gate(&self) -> bool {
+        self.0.is_gate()
+    }
 }
 
 macro_rules! impl_ops {
@@ -362,6 +728,10 @@ macro_rules! impl_ops {
         {
             type Input = Y::Input;
             type Output = <X::Output as core::ops::$an<X::Output>>::Output;
+            fn reset(&mut self) {
+                self.x.reset();
+                self.y.reset();
+            }
             fn sample(&mut self, config: &Config, _: Self::Input) -> Self::Output {
                 use core::ops::$an;
                 let x = self.x.sample(config, ());
@@ -399,6 +769,11 @@ where
     type Input = X::Input;
     type Output = Y::Output;
 
+    fn reset(&mut self) {
+        self.x.reset();
+        self.y.reset();
+    }
+
     fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
         let yinput = self.x.sample(config, input);
         self.y.sample(config, yinput)
@@ -429,6 +804,11 @@ where
     type Input = X::Input;
     type Output = F<{ X_OUT + Y_OUT }>;
 
+    fn reset(&mut self) {
+        self.x.reset();
+        self.y.reset();
+    }
+
     fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
         let x_out = self.x.sample(config, input);
         let y_out = self.y.sample(config, input);
@@ -484,6 +864,11 @@ where
 {
     type Input = Src::Input;
     type Output = Src::Output;
+    fn reset(&mut self) {
+        self.src.reset();
+        self.trig.reset();
+        self.triggered = false;
+    }
     fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
         let trigger = self.trig.sample(config, ())[0];
         if !self.triggered && trigger > 0.0 {
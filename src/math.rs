@@ -17,3 +17,25 @@ impl IntoSignal for i32 {
 pub fn clamp(f: f32) -> f32 {
     f.clamp(-1.0, 1.0)
 }
+
+pub fn hermite(t: f32, x0: f32, x1: f32, x2: f32, x3: f32) -> f32 {
+    let c1 = 0.5 * (x2 - x0);
+    let c2 = x0 - 2.5 * x1 + 2.0 * x2 - 0.5 * x3;
+    let c3 = 0.5 * (x3 - x0) + 1.5 * (x1 - x2);
+    ((c3 * t + c2) * t + c1) * t + x1
+}
+
+/// Equal-power gains for a pan position from -1 to 1.
+pub fn pan(pan: f32) -> (f32, f32) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * core::f32::consts::FRAC_PI_4;
+    let (right, left) = angle.sin_cos();
+    (left, right)
+}
+
+pub fn db(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+pub fn midi_hz(note: f32) -> f32 {
+    440.0 * 2f32.powf((note - 69.0) / 12.0)
+}
@@ -0,0 +1,129 @@
+use crate::{An, Config, F, IntoStereo, Process, math, process};
+
+/// Mixer settings of a track or bus in the `acid!` macro.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Strip {
+    /// Gain in dB.
+    pub gain: f32,
+    pub pan: f32,
+    pub mute: bool,
+    /// When any track is soloed, only soloed tracks are heard.
+    pub solo: bool,
+}
+
+impl Default for Strip {
+    fn default() -> Self {
+        Self {
+            gain: 0.0,
+            pan: 0.0,
+            mute: false,
+            solo: false,
+        }
+    }
+}
+
+const SMOOTHING: f64 = 0.01;
+
+pub type Bus = Box<dyn Process<Input = F<2>, Output = F<2>>>;
+
+pub fn bus<P>(effect: P) -> Bus
+where
+    P: Process<Input = F<2>> + 'static,
+    P::Output: IntoStereo + 'static,
+{
+    Box::new(An(effect) >> process(|_, frame: P::Output| frame.into_stereo()))
+}
+
+pub struct Channel {
+    pub name: &'static str,
+    pub strip: Strip,
+    /// Post-fader send levels in dB, one per bus.
+    pub sends: Vec<f32>,
+    declared: (Strip, Vec<f32>),
+    gains: [f32; 2],
+    send_gains: Vec<f32>,
+}
+
+impl Channel {
+    pub fn new(name: &'static str, strip: Strip, sends: Vec<f32>) -> Self {
+        Self {
+            name,
+            strip,
+            declared: (strip, sends.clone()),
+            gains: [0.0; 2],
+            send_gains: vec![0.0; sends.len()],
+            sends,
+        }
+    }
+
+    /// Keep runtime changes unless the declared strip or sends changed.
+    pub fn retain(&mut self, old: &Channel) {
+        if self.declared == old.declared {
+            self.strip = old.strip;
+            self.sends.clone_from(&old.sends);
+        }
+        self.gains = old.gains;
+        if self.send_gains.len() == old.send_gains.len() {
+            self.send_gains.clone_from(&old.send_gains);
+        }
+    }
+
+    pub fn audible(&self, solo: bool) -> bool {
+        !self.strip.mute && (!solo || self.strip.solo)
+    }
+
+    pub fn process(
+        &mut self,
+        config: &Config,
+        frame: F<2>,
+        solo: bool,
+        buses: &mut [F<2>],
+    ) -> F<2> {
+        let target = if self.audible(solo) {
+            let gain = math::db(self.strip.gain);
+            let (left, right) = math::pan(self.strip.pan);
+            [
+                (left * core::f32::consts::SQRT_2).min(1.0) * gain,
+                (right * core::f32::consts::SQRT_2).min(1.0) * gain,
+            ]
+        } else {
+            [0.0; 2]
+        };
+        let coefficient = (config.sample_duration / SMOOTHING).min(1.0) as f32;
+        for (gain, target) in self.gains.iter_mut().zip(target) {
+            *gain += (target - *gain) * coefficient;
+        }
+        let output = F([frame[0] * self.gains[0], frame[1] * self.gains[1]]);
+        for ((gain, send), bus) in self
+            .send_gains
+            .iter_mut()
+            .zip(self.sends.iter())
+            .zip(buses.iter_mut())
+        {
+            *gain += (math::db(*send) - *gain) * coefficient;
+            *bus = F([bus[0] + output[0] * *gain, bus[1] + output[1] * *gain]);
+        }
+        output
+    }
+}
+
+pub fn send<'a>(
+    channels: &'a mut [Channel],
+    returns: &[Channel],
+    track: &str,
+    bus: &str,
+) -> Option<&'a mut f32> {
+    let index = returns.iter().position(|channel| channel.name == bus)?;
+    channels
+        .iter_mut()
+        .find(|channel| channel.name == track)?
+        .sends
+        .get_mut(index)
+}
+
+pub fn strip<'a>(channels: &'a mut [Channel], name: &str) -> Option<&'a mut Strip> {
+    channels
+        .iter_mut()
+        .find(|channel| channel.name == name)
+        .map(|channel| &mut channel.strip)
+}
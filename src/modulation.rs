@@ -0,0 +1,361 @@
+use crate::delay::DelayLine;
+use crate::filter::Allpass;
+use crate::osc::{LfoPhase, LfoRate, LfoShape};
+use crate::{An, Config, F, IntoStereo, MonoSrcBound, Process, math};
+use std::marker::PhantomData;
+
+/// Sine at `phase` from 0 to 1, starting at 0.
+fn sweep(phase: f64) -> f64 {
+    0.5 + 0.5 * LfoShape::Sine.at(phase - 0.25) as f64
+}
+
+macro_rules! impl_rate_mix {
+    ($effect:ident { $($field:ident),* }) => {
+        impl<In, Rate, Depth, Mix> An<$effect<In, Rate, Depth, Mix>>
+        where
+            In: IntoStereo,
+            Rate: MonoSrcBound,
+            Depth: MonoSrcBound,
+            Mix: MonoSrcBound,
+        {
+            pub fn rate<Rate1>(self, hz: Rate1) -> An<$effect<In, Rate1, Depth, Mix>>
+            where
+                Rate1: MonoSrcBound,
+            {
+                An($effect {
+                    lfo: LfoPhase::new(LfoRate::Hz),
+                    rate: hz,
+                    depth: self.0.depth,
+                    mix: self.0.mix,
+                    $($field: self.0.$field,)*
+                    _in: PhantomData,
+                })
+            }
+
+            /// LFO cycle length in beats.
+            pub fn sync<Rate1>(self, beats: Rate1) -> An<$effect<In, Rate1, Depth, Mix>>
+            where
+                Rate1: MonoSrcBound,
+            {
+                An($effect {
+                    lfo: LfoPhase::new(LfoRate::Beats),
+                    rate: beats,
+                    depth: self.0.depth,
+                    mix: self.0.mix,
+                    $($field: self.0.$field,)*
+                    _in: PhantomData,
+                })
+            }
+
+            pub fn mix<Mix1>(self, mix: Mix1) -> An<$effect<In, Rate, Depth, Mix1>>
+            where
+                Mix1: MonoSrcBound,
+            {
+                An($effect {
+                    lfo: self.0.lfo,
+                    rate: self.0.rate,
+                    depth: self.0.depth,
+                    mix,
+                    $($field: self.0.$field,)*
+                    _in: PhantomData,
+                })
+            }
+
+            #[doc = concat!("Modulation depth, see [`", stringify!($effect), "`].")]
+            pub fn depth<Depth1>(self, depth: Depth1) -> An<$effect<In, Rate, Depth1, Mix>>
+            where
+                Depth1: MonoSrcBound,
+            {
+                An($effect {
+                    lfo: self.0.lfo,
+                    rate: self.0.rate,
+                    depth,
+                    mix: self.0.mix,
+                    $($field: self.0.$field,)*
+                    _in: PhantomData,
+                })
+            }
+        }
+    };
+}
+
+const CHORUS_DELAY: f64 = 0.012;
+
+/// Stereo chorus of voices swept at evenly spaced phases and spread from left
+/// to right.
+pub fn chorus<In>() -> An<Chorus<In, f32, f32, f32>>
+where
+    In: IntoStereo,
+{
+    An(Chorus {
+        lfo: LfoPhase::new(LfoRate::Hz),
+        line: DelayLine::default(),
+        voices: 3,
+        rate: 0.3,
+        depth: 0.004,
+        mix: 0.5,
+        _in: PhantomData,
+    })
+}
+
+/// `depth` is the sweep of each voice's delay in seconds.
+pub struct Chorus<In, Rate, Depth, Mix> {
+    lfo: LfoPhase,
+    line: DelayLine,
+    voices: usize,
+    rate: Rate,
+    depth: Depth,
+    mix: Mix,
+    _in: PhantomData<In>,
+}
+
+impl_rate_mix!(Chorus { line, voices });
+
+impl<In, Rate, Depth, Mix> An<Chorus<In, Rate, Depth, Mix>>
+where
+    In: IntoStereo,
+    Rate: MonoSrcBound,
+    Depth: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    pub fn voices(mut self, voices: usize) -> Self {
+        self.0.voices = voices.max(1);
+        self
+    }
+}
+
+impl<In, Rate, Depth, Mix> Process for Chorus<In, Rate, Depth, Mix>
+where
+    In: IntoStereo,
+    Rate: MonoSrcBound,
+    Depth: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    type Input = In;
+    type Output = F<2>;
+
+    fn reset(&mut self) {
+        self.line.clear();
+        self.lfo.reset();
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let rate = self.rate.filter_mono(config) as f64;
+        let depth = self.depth.filter_mono(config).max(0.0) as f64;
+        let mix = self.mix.filter_mono(config);
+
+        let input = input.into_stereo();
+        let phase = self.lfo.advance(config, rate);
+        self.line
+            .reserve((CHORUS_DELAY + depth) * config.sample_rate);
+        let mut wet = F([0.0; 2]);
+        for voice in 0..self.voices {
+            let offset = voice as f64 / self.voices as f64;
+            let delay = CHORUS_DELAY + depth * sweep(phase + offset);
+            let sample = self.line.tap(delay * config.sample_rate);
+            let pan = if self.voices == 1 {
+                0.0
+            } else {
+                voice as f32 / (self.voices - 1) as f32 * 2.0 - 1.0
+            };
+            let (left, right) = math::pan(pan);
+            wet[0] += sample * left;
+            wet[1] += sample * right;
+        }
+        self.line.write((input[0] + input[1]) * 0.5);
+
+        let gain = (self.voices as f32).sqrt().recip();
+        F([
+            input[0] * (1.0 - mix) + wet[0] * gain * mix,
+            input[1] * (1.0 - mix) + wet[1] * gain * mix,
+        ])
+    }
+}
+
+const FLANGER_DELAY: (f64, f64) = (0.0005, 0.006);
+
+pub fn flanger<In>() -> An<Flanger<In, f32, f32, f32>>
+where
+    In: IntoStereo,
+{
+    An(Flanger {
+        lfo: LfoPhase::new(LfoRate::Hz),
+        lines: Default::default(),
+        feedback: 0.5,
+        rate: 0.2,
+        depth: 1.0,
+        mix: 0.5,
+        _in: PhantomData,
+    })
+}
+
+pub struct Flanger<In, Rate, Depth, Mix> {
+    lfo: LfoPhase,
+    lines: [DelayLine; 2],
+    feedback: f32,
+    rate: Rate,
+    depth: Depth,
+    mix: Mix,
+    _in: PhantomData<In>,
+}
+
+impl_rate_mix!(Flanger { lines, feedback });
+
+impl<In, Rate, Depth, Mix> An<Flanger<In, Rate, Depth, Mix>>
+where
+    In: IntoStereo,
+    Rate: MonoSrcBound,
+    Depth: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    /// Feedback from -0.95 to 0.95.
+    pub fn feedback(mut self, feedback: f32) -> Self {
+        self.0.feedback = feedback.clamp(-0.95, 0.95);
+        self
+    }
+}
+
+impl<In, Rate, Depth, Mix> Process for Flanger<In, Rate, Depth, Mix>
+where
+    In: IntoStereo,
+    Rate: MonoSrcBound,
+    Depth: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    type Input = In;
+    type Output = F<2>;
+
+    fn reset(&mut self) {
+        for line in self.lines.iter_mut() {
+            line.clear();
+        }
+        self.lfo.reset();
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let rate = self.rate.filter_mono(config) as f64;
+        let depth = self.depth.filter_mono(config).clamp(0.0, 1.0) as f64;
+        let mix = self.mix.filter_mono(config);
+
+        let input = input.into_stereo();
+        let phase = self.lfo.advance(config, rate);
+        let (min, max) = FLANGER_DELAY;
+        let mut output = F([0.0; 2]);
+        for (channel, line) in self.lines.iter_mut().enumerate() {
+            let sweep = sweep(phase + channel as f64 * 0.25);
+            let delay = (min + (max - min) * depth * sweep) * config.sample_rate;
+            line.reserve(max * config.sample_rate);
+            let wet = line.tap(delay);
+            line.write(input[channel] + wet * self.feedback);
+            output[channel] = input[channel] * (1.0 - mix) + wet * mix;
+        }
+        output
+    }
+}
+
+const MAX_STAGES: usize = 12;
+
+pub fn phaser<In>() -> An<Phaser<In, f32, f32, f32>>
+where
+    In: IntoStereo,
+{
+    An(Phaser {
+        lfo: LfoPhase::new(LfoRate::Hz),
+        stages: [[Allpass::default(); MAX_STAGES]; 2],
+        len: 4,
+        range: (200.0, 4_000.0),
+        feedback: 0.3,
+        last: [0.0; 2],
+        rate: 0.3,
+        depth: 1.0,
+        mix: 0.5,
+        _in: PhantomData,
+    })
+}
+
+pub struct Phaser<In, Rate, Depth, Mix> {
+    lfo: LfoPhase,
+    stages: [[Allpass; MAX_STAGES]; 2],
+    len: usize,
+    range: (f64, f64),
+    feedback: f32,
+    last: [f32; 2],
+    rate: Rate,
+    depth: Depth,
+    mix: Mix,
+    _in: PhantomData<In>,
+}
+
+impl_rate_mix!(Phaser {
+    stages,
+    len,
+    range,
+    feedback,
+    last
+});
+
+impl<In, Rate, Depth, Mix> An<Phaser<In, Rate, Depth, Mix>>
+where
+    In: IntoStereo,
+    Rate: MonoSrcBound,
+    Depth: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    /// Number of allpass stages, up to 12.
+    pub fn stages(mut self, stages: usize) -> Self {
+        self.0.len = stages.clamp(1, MAX_STAGES);
+        self
+    }
+
+    pub fn range(mut self, min_hz: f32, max_hz: f32) -> Self {
+        self.0.range = (min_hz as f64, max_hz as f64);
+        self
+    }
+
+    pub fn feedback(mut self, feedback: f32) -> Self {
+        self.0.feedback = feedback.clamp(-0.95, 0.95);
+        self
+    }
+}
+
+impl<In, Rate, Depth, Mix> Process for Phaser<In, Rate, Depth, Mix>
+where
+    In: IntoStereo,
+    Rate: MonoSrcBound,
+    Depth: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    type Input = In;
+    type Output = F<2>;
+
+    fn reset(&mut self) {
+        for stage in self.stages.iter_mut().flatten() {
+            stage.reset();
+        }
+        self.last = [0.0; 2];
+        self.lfo.reset();
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let rate = self.rate.filter_mono(config) as f64;
+        let depth = self.depth.filter_mono(config).clamp(0.0, 1.0) as f64;
+        let mix = self.mix.filter_mono(config);
+
+        let input = input.into_stereo();
+        let phase = self.lfo.advance(config, rate);
+        let (min, max) = self.range;
+        let mut output = F([0.0; 2]);
+        for channel in 0..2 {
+            let sweep = sweep(phase + channel as f64 * 0.25) * depth;
+            let freq = min * (max / min).powf(sweep);
+            let coefficient = Allpass::coefficient(freq, config.sample_rate);
+            let mut wet = input[channel] + self.last[channel] * self.feedback;
+            for stage in self.stages[channel][..self.len].iter_mut() {
+                wet = stage.filter(wet, coefficient);
+            }
+            self.last[channel] = wet;
+            output[channel] = input[channel] * (1.0 - mix) + wet * mix;
+        }
+        output
+    }
+}
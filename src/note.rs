@@ -1,9 +1,14 @@
-use crate::{An, Config, F, MonoProcess, MonoSrc, Process, fmono, process, src};
+use crate::groove::Groove;
+use crate::math::IntoSignal;
+use crate::transport::Dur;
+use crate::{An, Config, F, Frame, MonoProcess, MonoSrcBound, Process, fmono, process};
+use core::ops::{Range, RangeInclusive};
 
 pub fn sample(mut seq: impl Sequence) -> An<MonoProcess<impl FnMut(&Config, F<1>) -> F<1>>> {
-    let len = seq.len() as f32;
+    let len = seq.len();
+    assert!(len > 0, "`sample` needs a non-empty sequence");
     process(move |_, f: F<1>| {
-        let index = (f[0].clamp(0.0, 1.0) * len) as usize;
+        let index = ((f[0].clamp(0.0, 1.0) * len as f32) as usize).min(len - 1);
         let sample = seq.sample_index(index);
         fmono(sample)
     })
@@ -21,45 +26,174 @@ pub fn dphrydom() -> An<MonoProcess<impl FnMut(&Config, F<1>) -> F<1>>> {
     sample((146.83, 155.56, 185, 196, 220, 233.08, 261.63, 293.66))
 }
 
-pub fn seq(mut seq: impl Sequence) -> An<MonoSrc<impl FnMut(&Config) -> F<1> + Copy>> {
-    let mut index = 0;
-    let len = seq.len();
-    src(move |_| {
-        let sample = seq.sample_index(index);
-        index = (index + 1) % len;
+/// Step through `seq`, one value per sample or one per step under `.seg()`.
+pub fn seq<S: Sequence>(seq: S) -> An<Seq<S>> {
+    assert!(seq.len() > 0, "`seq` needs a non-empty sequence");
+    An(Seq { seq, index: 0 })
+}
+
+#[derive(Clone, Copy)]
+pub struct Seq<S> {
+    seq: S,
+    index: usize,
+}
+
+impl<S: Sequence> Process for Seq<S> {
+    type Input = ();
+    type Output = F<1>;
+
+    fn reset(&mut self) {
+        self.index = 0;
+    }
+
+    fn sample(&mut self, config: &Config, _: Self::Input) -> Self::Output {
+        let len = self.seq.len();
+        let index = step_index(config, self.index, len);
+        let sample = self.seq.sample_index(index);
+        self.index = (index + 1) % len;
         fmono(sample)
+    }
+}
+
+/// Euclidean rhythm of `hits` over `steps` rotated left by `rotation`, read
+/// at the start of every cycle.
+pub fn euclid<Hits, Steps, Rotation>(
+    hits: Hits,
+    steps: Steps,
+    rotation: Rotation,
+) -> An<Euclid<Hits, Steps, Rotation>>
+where
+    Hits: MonoSrcBound,
+    Steps: MonoSrcBound,
+    Rotation: MonoSrcBound,
+{
+    An(Euclid {
+        hits,
+        steps,
+        rotation,
+        pattern: Vec::new(),
+        shape: (0, 0),
+        rotate: 0,
+        index: 0,
     })
 }
 
+pub struct Euclid<Hits, Steps, Rotation> {
+    hits: Hits,
+    steps: Steps,
+    rotation: Rotation,
+    pattern: Vec<bool>,
+    shape: (usize, usize),
+    rotate: usize,
+    index: usize,
+}
+
+impl<Hits, Steps, Rotation> Process for Euclid<Hits, Steps, Rotation>
+where
+    Hits: MonoSrcBound,
+    Steps: MonoSrcBound,
+    Rotation: MonoSrcBound,
+{
+    type Input = ();
+    type Output = F<1>;
+
+    fn reset(&mut self) {
+        self.index = 0;
+    }
+
+    fn sample(&mut self, config: &Config, _: Self::Input) -> Self::Output {
+        let hits = self.hits.filter_mono(config).round().max(0.0) as usize;
+        let steps = self.steps.filter_mono(config).round().max(1.0) as usize;
+        let rotation = self.rotation.filter_mono(config).round() as i64;
+        let mut index = step_index(config, self.index, self.pattern.len());
+        if index == 0 || self.pattern.is_empty() {
+            if self.shape != (hits, steps) {
+                self.shape = (hits, steps);
+                self.pattern = bjorklund(hits, steps);
+            }
+            self.rotate = rotation.rem_euclid(steps as i64) as usize;
+            index = step_index(config, self.index, steps);
+        }
+        let len = self.pattern.len();
+        let hit = self.pattern[(index + self.rotate) % len];
+        self.index = (index + 1) % len;
+        fmono(hit as u32 as f32)
+    }
+
+    fn is_gate(&self) -> bool {
+        true
+    }
+}
+
+fn step_index(config: &Config, index: usize, len: usize) -> usize {
+    match config.step {
+        Some(step) if len > 0 => step.rem_euclid(len as i64) as usize,
+        _ => index,
+    }
+}
+
+fn bjorklund(hits: usize, steps: usize) -> Vec<bool> {
+    let hits = hits.min(steps);
+    let mut a = vec![vec![true]; hits];
+    let mut b = vec![vec![false]; steps - hits];
+    while b.len() > 1 && !a.is_empty() {
+        let pairs = a.len().min(b.len());
+        let rest = if a.len() > pairs {
+            a.split_off(pairs)
+        } else {
+            b.split_off(pairs)
+        };
+        for (a, b) in a.iter_mut().zip(b) {
+            a.extend(b);
+        }
+        b = rest;
+    }
+    a.into_iter().chain(b).flatten().collect()
+}
+
 crate::impl_wrapper_ext! {
     pub trait SegExt {
-        fn seg(self, seg: usize) -> An<Seg<Self>> {
+        fn seg(self, seg: impl Into<Dur>) -> An<Seg<Self>> {
             An(Seg::new(self, seg))
         }
+
+        /// Spread `steps` evenly over `span`, e.g. `.poly(3, 1)` for 3 against 4.
+        fn poly(self, steps: usize, span: impl Into<Dur>) -> An<Seg<Self>> {
+            An(Seg::new(self, span.into() / steps as f64))
+        }
     }
 }
 
 #[derive(Clone, Copy)]
 pub struct Seg<Src: Process> {
     src: Src,
-    seg: f64,
-    t: f64,
+    clock: Clock,
     retained: Src::Output,
-    init: bool,
 }
 
 impl<Src: Process> Seg<Src> {
-    pub fn new(src: Src, seg: usize) -> Self {
+    pub fn new(src: Src, seg: impl Into<Dur>) -> Self {
         Self {
             src,
-            seg: seg as f64,
-            t: 0.0,
+            clock: Clock::new(seg.into()),
             retained: Src::Output::default(),
-            init: false,
         }
     }
 }
 
+impl<Src: Process> An<Seg<Src>> {
+    pub fn swing(self, percent: f32) -> Self {
+        self.groove(Groove::swing(percent))
+    }
+
+    /// Shift the steps by the offsets of `groove`. Its velocities only accent
+    /// gates, see [`Trigs`](crate::trig::Trigs).
+    pub fn groove(mut self, groove: Groove) -> Self {
+        self.0.clock.groove = groove;
+        self
+    }
+}
+
 impl<Src> Process for Seg<Src>
 where
     Src: Process,
@@ -67,54 +201,169 @@ where
     type Input = Src::Input;
     type Output = Src::Output;
 
+    fn reset(&mut self) {
+        self.src.reset();
+        self.clock.reset();
+    }
+
     fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
-        let duration = config.spb / self.seg;
-        if !self.init || self.t >= duration {
-            self.init = true;
-            self.t -= duration;
-            self.retained = self.src.sample(config, input);
+        if self.clock.tick(config) {
+            let config = Config {
+                step: Some(self.clock.index()),
+                ..config.clone()
+            };
+            self.retained = self.src.sample(&config, input);
+        }
+        if self.src.is_gate() {
+            let next = self.clock.phase(config) + 1.0 / (self.clock.beats(config) * config.spb);
+            if next >= 1.0 {
+                return Src::Output::new();
+            }
         }
-        self.t += 1.0;
         self.retained
     }
 }
 
+#[derive(Clone, Copy)]
+pub(crate) struct Clock {
+    dur: Dur,
+    pub(crate) groove: Groove,
+    step: Option<i64>,
+    /// Step counted as the first, from the start of the transport until reset.
+    origin: Option<i64>,
+}
+
+impl Clock {
+    pub(crate) fn new(dur: Dur) -> Self {
+        Self {
+            dur,
+            groove: Groove::default(),
+            step: None,
+            origin: Some(0),
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.step = None;
+        self.origin = None;
+    }
+
+    pub(crate) fn tick(&mut self, config: &Config) -> bool {
+        let position = config.transport.position(self.dur);
+        let mut step = position.floor() as i64;
+        let offset = position - step as f64;
+        if offset < self.groove.offset(step) {
+            step -= 1;
+        } else if offset >= 1.0 + self.groove.offset(step + 1) {
+            step += 1;
+        }
+        let tick = self.step != Some(step);
+        self.step = Some(step);
+        self.origin.get_or_insert(step);
+        tick
+    }
+
+    pub(crate) fn step(&self) -> i64 {
+        self.step.unwrap_or(0)
+    }
+
+    /// Steps since the origin.
+    pub(crate) fn index(&self) -> i64 {
+        self.step() - self.origin.unwrap_or(0)
+    }
+
+    fn len(&self) -> f64 {
+        let step = self.step();
+        1.0 + self.groove.offset(step + 1) - self.groove.offset(step)
+    }
+
+    pub(crate) fn beats(&self, config: &Config) -> f64 {
+        self.dur.to_beats(config.transport.signature) * self.len()
+    }
+
+    pub(crate) fn phase(&self, config: &Config) -> f64 {
+        let step = self.step();
+        let start = step as f64 + self.groove.offset(step);
+        (config.transport.position(self.dur) - start) / self.len()
+    }
+}
+
+/// Indices wrap around the length of the sequence.
 #[allow(clippy::len_without_is_empty)]
-pub trait Sequence: Copy {
+pub trait Sequence: Clone {
     fn sample_index(&mut self, index: usize) -> f32;
     fn len(&self) -> usize;
 }
 
 macro_rules! impl_seq {
-    ($($param:ident),*) => {
-        #[allow(non_snake_case)]
+    ($($index:tt: $param:ident),*) => {
         impl<$($param: $crate::math::IntoSignal,)*> Sequence for ($($param),*) {
             fn sample_index(&mut self, index: usize) -> f32 {
-                let ($($param),*) = self;
-                let mut i = 0;
-                $(
-                    if i == index { return $param.into_f32(); }
-                    i += 1;
-                )*
-                panic!("index {} out of bounds for `Sequence::sample_index`", index);
+                match index % self.len() {
+                    $($index => self.$index.into_f32(),)*
+                    _ => unreachable!(),
+                }
             }
             fn len(&self) -> usize {
-                let ($($param),*) = self;
-                let mut len = 0;
-                $(
-                    _ = $param;
-                    len += 1;
-                )*
-                len
+                [$($index),*].len()
             }
         }
     };
 }
 
-impl_seq!(F1, F2);
-impl_seq!(F1, F2, F3);
-impl_seq!(F1, F2, F3, F4);
-impl_seq!(F1, F2, F3, F4, F5);
-impl_seq!(F1, F2, F3, F4, F5, F6);
-impl_seq!(F1, F2, F3, F4, F5, F6, F7);
-impl_seq!(F1, F2, F3, F4, F5, F6, F7, F8);
+impl_seq!(0: F1, 1: F2);
+impl_seq!(0: F1, 1: F2, 2: F3);
+impl_seq!(0: F1, 1: F2, 2: F3, 3: F4);
+impl_seq!(0: F1, 1: F2, 2: F3, 3: F4, 4: F5);
+impl_seq!(0: F1, 1: F2, 2: F3, 3: F4, 4: F5, 5: F6);
+impl_seq!(0: F1, 1: F2, 2: F3, 3: F4, 4: F5, 5: F6, 6: F7);
+impl_seq!(0: F1, 1: F2, 2: F3, 3: F4, 4: F5, 5: F6, 6: F7, 7: F8);
+impl_seq!(0: F1, 1: F2, 2: F3, 3: F4, 4: F5, 5: F6, 6: F7, 7: F8, 8: F9);
+impl_seq!(0: F1, 1: F2, 2: F3, 3: F4, 4: F5, 5: F6, 6: F7, 7: F8, 8: F9, 9: F10);
+impl_seq!(0: F1, 1: F2, 2: F3, 3: F4, 4: F5, 5: F6, 6: F7, 7: F8, 8: F9, 9: F10, 10: F11);
+impl_seq!(0: F1, 1: F2, 2: F3, 3: F4, 4: F5, 5: F6, 6: F7, 7: F8, 8: F9, 9: F10, 10: F11, 11: F12);
+
+impl<T: IntoSignal, const N: usize> Sequence for [T; N] {
+    fn sample_index(&mut self, index: usize) -> f32 {
+        self[index % N].into_f32()
+    }
+    fn len(&self) -> usize {
+        N
+    }
+}
+
+impl<T: IntoSignal> Sequence for Vec<T> {
+    fn sample_index(&mut self, index: usize) -> f32 {
+        self[index % self.len()].into_f32()
+    }
+    fn len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: IntoSignal> Sequence for &'static [T] {
+    fn sample_index(&mut self, index: usize) -> f32 {
+        self[index % <[T]>::len(self)].into_f32()
+    }
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+}
+
+impl Sequence for Range<i32> {
+    fn sample_index(&mut self, index: usize) -> f32 {
+        (self.start + (index % Sequence::len(self)) as i32) as f32
+    }
+    fn len(&self) -> usize {
+        (self.end - self.start).max(0) as usize
+    }
+}
+
+impl Sequence for RangeInclusive<i32> {
+    fn sample_index(&mut self, index: usize) -> f32 {
+        (self.start() + (index % Sequence::len(self)) as i32) as f32
+    }
+    fn len(&self) -> usize {
+        (self.end() - self.start() + 1).max(0) as usize
+    }
+}
@@ -1,4 +1,5 @@
-use crate::{Config, F, MonoProcess, MonoSrcBound, An, c, fmono, process};
+use crate::rng::{Rng, rand};
+use crate::{An, Config, F, MonoProcess, MonoSrcBound, Process, c, fmono, process};
 
 pub fn sin() -> An<MonoProcess<impl FnMut(&Config, F<1>) -> F<1>>> {
     let mut phase = 0f64;
@@ -29,3 +30,227 @@ pub fn saw() -> An<MonoProcess<impl FnMut(&Config, F<1>) -> F<1>>> {
 pub fn saw_hz(hz: f32) -> An<impl MonoSrcBound> {
     c(hz) >> saw()
 }
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    SawUp,
+    SawDown,
+    Square,
+    SampleHold,
+    SmoothRandom,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LfoRate {
+    Hz,
+    Beats,
+    Bars,
+}
+
+impl LfoShape {
+    /// Value of a periodic shape at `phase`, or 0 for the random shapes.
+    pub(crate) fn at(self, p: f64) -> f32 {
+        match self {
+            LfoShape::Sine => (core::f64::consts::TAU * p).sin() as f32,
+            LfoShape::Triangle => 1.0 - 4.0 * ((p + 0.25).fract() - 0.5).abs() as f32,
+            LfoShape::SawUp => p as f32 * 2.0 - 1.0,
+            LfoShape::SawDown => 1.0 - p as f32 * 2.0,
+            LfoShape::Square => {
+                if p < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::SampleHold | LfoShape::SmoothRandom => 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct LfoPhase {
+    pub(crate) unit: LfoRate,
+    pub(crate) offset: f64,
+    pub(crate) phase: f64,
+    /// Beats or bars of the transport at the offset, from the start until
+    /// reset.
+    origin: Option<f64>,
+}
+
+impl LfoPhase {
+    pub(crate) fn new(unit: LfoRate) -> Self {
+        Self {
+            unit,
+            offset: 0.0,
+            phase: 0.0,
+            origin: Some(0.0),
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.phase = self.offset;
+        self.origin = None;
+    }
+
+    /// Phase at the current sample, holding while `rate` is 0 or less. Synced
+    /// rates follow the transport.
+    pub(crate) fn advance(&mut self, config: &Config, rate: f64) -> f64 {
+        let position = match self.unit {
+            LfoRate::Hz => 0.0,
+            LfoRate::Beats => config.transport.beats,
+            LfoRate::Bars => config.transport.bars(),
+        };
+        let Some(origin) = self.origin else {
+            self.origin = Some(position);
+            return self.phase;
+        };
+        if rate > 0.0 {
+            self.phase = match self.unit {
+                LfoRate::Hz => (self.phase + rate * config.sample_duration).fract(),
+                LfoRate::Beats | LfoRate::Bars => {
+                    ((position - origin) / rate + self.offset).fract()
+                }
+            };
+        }
+        self.phase
+    }
+}
+
+/// Low frequency oscillator, bipolar unless `.unipolar()` is set.
+pub fn lfo(shape: LfoShape) -> An<Lfo<f32>> {
+    An(Lfo {
+        shape,
+        rate: 1.0,
+        phase: LfoPhase::new(LfoRate::Hz),
+        unipolar: false,
+        rng: rand(0),
+        from: 0.0,
+        to: 0.0,
+        fresh: true,
+    })
+}
+
+pub struct Lfo<Rate> {
+    shape: LfoShape,
+    rate: Rate,
+    phase: LfoPhase,
+    unipolar: bool,
+    rng: An<Rng>,
+    from: f32,
+    to: f32,
+    fresh: bool,
+}
+
+impl<Rate> An<Lfo<Rate>>
+where
+    Rate: MonoSrcBound,
+{
+    fn rate<Rate1>(self, rate: Rate1, unit: LfoRate) -> An<Lfo<Rate1>>
+    where
+        Rate1: MonoSrcBound,
+    {
+        An(Lfo {
+            shape: self.0.shape,
+            rate,
+            phase: LfoPhase {
+                unit,
+                ..self.0.phase
+            },
+            unipolar: self.0.unipolar,
+            rng: self.0.rng,
+            from: self.0.from,
+            to: self.0.to,
+            fresh: self.0.fresh,
+        })
+    }
+
+    pub fn hz<Rate1>(self, hz: Rate1) -> An<Lfo<Rate1>>
+    where
+        Rate1: MonoSrcBound,
+    {
+        self.rate(hz, LfoRate::Hz)
+    }
+
+    pub fn beats<Rate1>(self, beats: Rate1) -> An<Lfo<Rate1>>
+    where
+        Rate1: MonoSrcBound,
+    {
+        self.rate(beats, LfoRate::Beats)
+    }
+
+    pub fn bars<Rate1>(self, bars: Rate1) -> An<Lfo<Rate1>>
+    where
+        Rate1: MonoSrcBound,
+    {
+        self.rate(bars, LfoRate::Bars)
+    }
+
+    /// Starting phase in cycles.
+    pub fn phase(mut self, offset: f64) -> Self {
+        self.0.phase.offset = offset.rem_euclid(1.0);
+        self.0.phase.phase = self.0.phase.offset;
+        self
+    }
+
+    pub fn unipolar(mut self) -> Self {
+        self.0.unipolar = true;
+        self
+    }
+
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.0.rng = rand(seed);
+        self
+    }
+}
+
+impl<Rate> Lfo<Rate> {
+    fn random(&mut self, config: &Config) -> f32 {
+        self.rng.sample(config, ())[0] * 2.0 - 1.0
+    }
+}
+
+impl<Rate> Process for Lfo<Rate>
+where
+    Rate: MonoSrcBound,
+{
+    type Input = ();
+    type Output = F<1>;
+
+    fn reset(&mut self) {
+        self.phase.reset();
+        self.rng.reset();
+        self.fresh = true;
+    }
+
+    fn sample(&mut self, config: &Config, _: Self::Input) -> Self::Output {
+        if self.fresh {
+            self.fresh = false;
+            self.from = self.random(config);
+            self.to = self.random(config);
+        }
+
+        let rate = self.rate.filter_mono(config) as f64;
+        let last = self.phase.phase;
+        let p = self.phase.advance(config, rate);
+        if p < last {
+            self.from = self.to;
+            self.to = self.random(config);
+        }
+        let value = match self.shape {
+            LfoShape::SampleHold => self.to,
+            LfoShape::SmoothRandom => {
+                let t = (0.5 - 0.5 * (core::f64::consts::PI * p).cos()) as f32;
+                self.from + (self.to - self.from) * t
+            }
+            shape => shape.at(p),
+        };
+
+        fmono(if self.unipolar {
+            value * 0.5 + 0.5
+        } else {
+            value
+        })
+    }
+}
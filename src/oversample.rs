@@ -0,0 +1,273 @@
+use crate::{An, Config, Frame, Process};
+
+const TAPS: usize = 63;
+const CENTER: usize = TAPS / 2;
+
+pub const MAX_FACTOR: usize = 16;
+
+/// Linear-phase half-band lowpass storing only its non-zero taps.
+#[derive(Clone)]
+struct Halfband {
+    taps: Vec<(usize, f32)>,
+    history: [f32; TAPS],
+    index: usize,
+}
+
+impl Halfband {
+    fn new() -> Self {
+        let window = |n: f64| {
+            // 4-term Blackman-Harris
+            let x = core::f64::consts::TAU * n / (TAPS - 1) as f64;
+            0.35875 - 0.48829 * x.cos() + 0.14128 * (2.0 * x).cos() - 0.01168 * (3.0 * x).cos()
+        };
+        let taps = (0..TAPS)
+            .filter(|n| (n + CENTER) % 2 == 1 || *n == CENTER)
+            .map(|n| {
+                let m = n as f64 - CENTER as f64;
+                let sinc = if m == 0.0 {
+                    1.0
+                } else {
+                    let x = core::f64::consts::PI * m * 0.5;
+                    x.sin() / x
+                };
+                (n, 0.5 * sinc * window(n as f64))
+            })
+            .collect::<Vec<_>>();
+        // normalize to unity gain at DC
+        let sum = taps.iter().map(|(_, h)| h).sum::<f64>();
+        Self {
+            taps: taps
+                .into_iter()
+                .map(|(n, h)| (n, (h / sum) as f32))
+                .collect(),
+            history: [0.0; TAPS],
+            index: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history = [0.0; TAPS];
+        self.index = 0;
+    }
+
+    fn push(&mut self, sample: f32) {
+        self.index = (self.index + 1) % TAPS;
+        self.history[self.index] = sample;
+    }
+
+    fn filter(&mut self, sample: f32) -> f32 {
+        self.push(sample);
+        self.taps
+            .iter()
+            .map(|&(n, h)| self.history[(self.index + TAPS - n) % TAPS] * h)
+            .sum()
+    }
+}
+
+fn stages(factor: usize) -> Vec<Halfband> {
+    assert!(
+        factor.is_power_of_two() && factor <= MAX_FACTOR,
+        "oversampling factor must be a power of two no greater than {MAX_FACTOR}"
+    );
+    (0..factor.trailing_zeros())
+        .map(|_| Halfband::new())
+        .collect()
+}
+
+/// Delay in samples at the base rate of an up and down pair of `factor`.
+pub fn latency(factor: usize) -> f64 {
+    (1..=factor.trailing_zeros())
+        .map(|stage| (2 * CENTER - 1) as f64 / (1 << stage) as f64)
+        .sum()
+}
+
+#[derive(Clone)]
+pub struct Upsampler {
+    stages: Vec<Halfband>,
+}
+
+impl Upsampler {
+    pub fn new(factor: usize) -> Self {
+        Self {
+            stages: stages(factor),
+        }
+    }
+
+    pub fn factor(&self) -> usize {
+        1 << self.stages.len()
+    }
+
+    pub fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+    }
+
+    pub fn process(&mut self, sample: f32, output: &mut [f32]) {
+        debug_assert_eq!(output.len(), self.factor());
+        let mut buffer = [0.0; MAX_FACTOR];
+        let mut len = 1;
+        output[0] = sample;
+        for stage in self.stages.iter_mut() {
+            buffer[..len].copy_from_slice(&output[..len]);
+            for (i, sample) in buffer[..len].iter().enumerate() {
+                // zero stuffing halves the gain
+                output[i * 2] = stage.filter(sample * 2.0);
+                output[i * 2 + 1] = stage.filter(0.0);
+            }
+            len *= 2;
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Downsampler {
+    stages: Vec<Halfband>,
+}
+
+impl Downsampler {
+    pub fn new(factor: usize) -> Self {
+        Self {
+            stages: stages(factor),
+        }
+    }
+
+    pub fn factor(&self) -> usize {
+        1 << self.stages.len()
+    }
+
+    pub fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+    }
+
+    pub fn process(&mut self, input: &mut [f32]) -> f32 {
+        debug_assert_eq!(input.len(), self.factor());
+        let mut len = input.len();
+        for stage in self.stages.iter_mut() {
+            len /= 2;
+            for i in 0..len {
+                stage.push(input[i * 2]);
+                input[i] = stage.filter(input[i * 2 + 1]);
+            }
+        }
+        input[0]
+    }
+}
+
+crate::impl_wrapper_ext! {
+    pub trait OversampleExt {
+        /// Run `self` at `factor` times the sample rate.
+        fn oversample(self, factor: usize) -> An<Oversample<Self>> {
+            An(Oversample::new(self, factor))
+        }
+    }
+}
+
+pub struct Oversample<P> {
+    process: P,
+    factor: usize,
+    up: Vec<Upsampler>,
+    down: Vec<Downsampler>,
+    input: Vec<f32>,
+    output: Vec<f32>,
+}
+
+impl<P: Process> Oversample<P> {
+    pub fn new(process: P, factor: usize) -> Self {
+        let inputs = P::Input::channels();
+        let outputs = P::Output::channels();
+        Self {
+            process,
+            factor,
+            up: (0..inputs).map(|_| Upsampler::new(factor)).collect(),
+            down: (0..outputs).map(|_| Downsampler::new(factor)).collect(),
+            input: vec![0.0; inputs * factor],
+            output: vec![0.0; outputs * factor],
+        }
+    }
+}
+
+impl<P: Process> Process for Oversample<P> {
+    type Input = P::Input;
+    type Output = P::Output;
+
+    fn reset(&mut self) {
+        self.process.reset();
+        for up in self.up.iter_mut() {
+            up.reset();
+        }
+        for down in self.down.iter_mut() {
+            down.reset();
+        }
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let factor = self.factor;
+        let mut oversampled = config.clone();
+        oversampled.sample_rate *= factor as f64;
+        oversampled.sample_duration /= factor as f64;
+        oversampled.spb *= factor as f64;
+        oversampled.bps /= factor as f64;
+
+        for ((up, buffer), sample) in self
+            .up
+            .iter_mut()
+            .zip(self.input.chunks_mut(factor))
+            .zip(input.as_slice())
+        {
+            up.process(*sample, buffer);
+        }
+
+        for i in 0..factor {
+            let mut frame = Self::Input::new();
+            for (channel, sample) in frame.as_mut_slice().iter_mut().enumerate() {
+                *sample = self.input[channel * factor + i];
+            }
+            let frame = self.process.sample(&oversampled, frame);
+            for (channel, sample) in frame.as_slice().iter().enumerate() {
+                self.output[channel * factor + i] = *sample;
+            }
+        }
+
+        let mut output = Self::Output::new();
+        for ((down, buffer), sample) in self
+            .down
+            .iter_mut()
+            .zip(self.output.chunks_mut(factor))
+            .zip(output.as_mut_slice())
+        {
+            *sample = down.process(buffer);
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency() {
+        for factor in [2, 4, 8, 16] {
+            let mut up = Upsampler::new(factor);
+            let mut down = Downsampler::new(factor);
+            let mut buffer = vec![0.0; factor];
+            // the response is symmetric, so its centroid is its delay
+            let (mut sum, mut moment) = (0.0, 0.0);
+            for n in 0..256 {
+                up.process(if n == 0 { 1.0 } else { 0.0 }, &mut buffer);
+                let sample = down.process(&mut buffer) as f64;
+                sum += sample;
+                moment += sample * n as f64;
+            }
+            let delay = moment / sum;
+            assert!(
+                (delay - super::latency(factor)).abs() < 1e-3,
+                "{factor}x delays by {delay}, not {}",
+                super::latency(factor)
+            );
+        }
+    }
+}
@@ -0,0 +1,510 @@
+use crate::transport::Dur;
+use crate::{An, Config, F, Process, fmono};
+use std::io::{Error, ErrorKind};
+
+/// Gate pattern in mini-notation over one bar, or `.beats()`:
+///
+/// - `x` or any word is an event at level 1, numbers at that level
+/// - `~` is a rest
+/// - `[a b]` plays a subsequence within one step
+/// - `<a b>` plays one element per cycle
+/// - `a*2` and `a/2` play a step twice and half as fast
+/// - `a!3` repeats a step, and a lone `!` repeats the last step
+/// - `a@3` stretches a step to three steps
+/// - `{a b c}%4` plays the steps at 4 per cycle
+/// - `[a b, c d e]` plays layers at once, and `{a b c, d e}` at the steps of
+///   the first layer
+pub fn pat(src: &str) -> std::io::Result<An<Pat>> {
+    Pat::parse(src, Mode::Gate, gate).map(An)
+}
+
+/// Frequency pattern of note names like `eb2` or MIDI numbers, holding the
+/// last note through rests.
+pub fn notes(src: &str) -> std::io::Result<An<Pat>> {
+    Pat::parse(src, Mode::Hold, note).map(An)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Gate,
+    Hold,
+}
+
+#[derive(Clone)]
+pub struct Pat {
+    root: Node,
+    mode: Mode,
+    beats: Option<f64>,
+    origin: Option<f64>,
+    held: f32,
+}
+
+impl Pat {
+    fn parse(src: &str, mode: Mode, word: fn(&str) -> Option<f32>) -> std::io::Result<Self> {
+        let root = Parser::parse(src, word).map_err(|error| {
+            Error::new(ErrorKind::InvalidData, format!("pattern {src:?}: {error}"))
+        })?;
+        Ok(Self {
+            root,
+            mode,
+            beats: None,
+            origin: Some(0.0),
+            held: 0.0,
+        })
+    }
+}
+
+impl An<Pat> {
+    pub fn beats(mut self, beats: f64) -> Self {
+        self.0.beats = Some(beats);
+        self
+    }
+}
+
+impl Process for Pat {
+    type Input = ();
+    type Output = F<1>;
+
+    fn reset(&mut self) {
+        self.origin = None;
+    }
+
+    fn sample(&mut self, config: &Config, _: Self::Input) -> Self::Output {
+        // cycles of a bar follow the bar line across time signature changes
+        let cycle = match self.beats {
+            Some(beats) => Dur::beats(beats),
+            None => Dur::bars(1.0),
+        };
+        let origin = *self.origin.get_or_insert(cycle.steps(&config.transport));
+        let t = config.transport.position(cycle) - origin;
+        let step = 1.0 / (cycle.to_beats(config.transport.signature) * config.spb);
+        let event = self.root.query(t);
+        let output = match self.mode {
+            // drop on the last sample of an event so the next one retriggers
+            Mode::Gate => match event {
+                Some(event)
+                    if self
+                        .root
+                        .query(t + step)
+                        .is_some_and(|next| next.begin == event.begin) =>
+                {
+                    event.value
+                }
+                _ => 0.0,
+            },
+            Mode::Hold => {
+                if let Some(event) = event {
+                    self.held = event.value;
+                }
+                self.held
+            }
+        };
+        fmono(output)
+    }
+}
+
+fn gate(word: &str) -> Option<f32> {
+    Some(word.parse().unwrap_or(1.0))
+}
+
+fn note(word: &str) -> Option<f32> {
+    if let Ok(midi) = word.parse::<f32>() {
+        return Some(crate::math::midi_hz(midi));
+    }
+    let mut chars = word.chars().peekable();
+    let mut semitone = match chars.next()?.to_ascii_lowercase() {
+        'c' => 0,
+        'd' => 2,
+        'e' => 4,
+        'f' => 5,
+        'g' => 7,
+        'a' => 9,
+        'b' => 11,
+        _ => return None,
+    };
+    match chars.peek() {
+        Some('#' | 's') => {
+            semitone += 1;
+            chars.next();
+        }
+        Some('b') => {
+            semitone -= 1;
+            chars.next();
+        }
+        _ => {}
+    }
+    let octave = chars.collect::<String>();
+    let octave = if octave.is_empty() {
+        3
+    } else {
+        octave.parse::<i32>().ok()?
+    };
+    Some(crate::math::midi_hz((12 * (octave + 1) + semitone) as f32))
+}
+
+#[derive(Clone)]
+enum Node {
+    Rest,
+    Value(f32),
+    Cat(Vec<(Node, f64)>),
+    Alt(Vec<Node>),
+    Fast(Box<Node>, f64),
+    Stack(Vec<Node>),
+}
+
+struct Event {
+    value: f32,
+    begin: f64,
+}
+
+impl Node {
+    fn query(&self, t: f64) -> Option<Event> {
+        match self {
+            Self::Rest => None,
+            Self::Value(value) => Some(Event {
+                value: *value,
+                begin: t.floor(),
+            }),
+            Self::Cat(steps) => {
+                let cycle = t.floor();
+                let total = steps.iter().map(|(_, weight)| weight).sum::<f64>();
+                let position = (t - cycle) * total;
+                let mut start = 0.0;
+                for (node, weight) in steps {
+                    if position < start + weight {
+                        // the step sees the same cycle number as its parent
+                        let (offset, width) = (start / total, weight / total);
+                        let local = cycle + (position - start) / weight;
+                        let event = node.query(local)?;
+                        // only the fragment with the onset plays, e.g. of `a/2`
+                        if event.begin < cycle {
+                            return None;
+                        }
+                        return Some(Event {
+                            value: event.value,
+                            begin: cycle + offset + (event.begin - cycle) * width,
+                        });
+                    }
+                    start += weight;
+                }
+                None
+            }
+            Self::Alt(nodes) => {
+                let cycle = t.floor();
+                let len = nodes.len() as f64;
+                let node = &nodes[cycle.rem_euclid(len) as usize];
+                // each element sees its own consecutive cycles
+                let shift = cycle - cycle.div_euclid(len);
+                let event = node.query(t - shift)?;
+                Some(Event {
+                    value: event.value,
+                    begin: event.begin + shift,
+                })
+            }
+            Self::Fast(node, factor) => {
+                let event = node.query(t * factor)?;
+                Some(Event {
+                    value: event.value,
+                    begin: event.begin / factor,
+                })
+            }
+            // the latest onset wins, and the first layer on a tie
+            Self::Stack(nodes) => nodes.iter().filter_map(|node| node.query(t)).fold(
+                None,
+                |latest: Option<Event>, event| match latest {
+                    Some(latest) if latest.begin >= event.begin => Some(latest),
+                    _ => Some(event),
+                },
+            ),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    word: fn(&str) -> Option<f32>,
+}
+
+impl Parser {
+    fn parse(src: &str, word: fn(&str) -> Option<f32>) -> Result<Node, String> {
+        let mut parser = Self {
+            chars: src.chars().collect(),
+            pos: 0,
+            word,
+        };
+        let layers = parser.layers()?;
+        match parser.peek() {
+            Some(c) => Err(parser.error(&format!("unexpected `{c}`"))),
+            None => Ok(stack(layers.into_iter().map(cat).collect())),
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{message} at {}", self.pos)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let eaten = self.peek() == Some(c);
+        if eaten {
+            self.pos += 1;
+        }
+        eaten
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{c}`")))
+        }
+    }
+
+    fn steps(&mut self) -> Result<Vec<(Node, f64)>, String> {
+        let mut steps = Vec::new();
+        loop {
+            while self.peek().is_some_and(char::is_whitespace) {
+                self.pos += 1;
+            }
+            match self.peek() {
+                None | Some(']' | '>' | '}' | ',') => return Ok(steps),
+                Some('!') => {
+                    self.pos += 1;
+                    let last = steps
+                        .last()
+                        .cloned()
+                        .ok_or_else(|| self.error("nothing to repeat"))?;
+                    steps.push(last);
+                }
+                _ => {
+                    let mut node = self.term()?;
+                    let mut weight = 1.0;
+                    let mut repeats = 1;
+                    loop {
+                        if self.eat('*') {
+                            node = Node::Fast(Box::new(node), self.number()?);
+                        } else if self.eat('/') {
+                            node = Node::Fast(Box::new(node), 1.0 / self.number()?);
+                        } else if self.eat('@') {
+                            weight = self.number()?;
+                        } else if self.peek() == Some('!')
+                            && self
+                                .chars
+                                .get(self.pos + 1)
+                                .is_some_and(char::is_ascii_digit)
+                        {
+                            self.pos += 1;
+                            repeats = self.number()? as usize;
+                        } else {
+                            break;
+                        }
+                    }
+                    for _ in 0..repeats {
+                        steps.push((node.clone(), weight));
+                    }
+                }
+            }
+        }
+    }
+
+    fn layers(&mut self) -> Result<Vec<Vec<(Node, f64)>>, String> {
+        let mut layers = vec![self.steps()?];
+        while self.eat(',') {
+            layers.push(self.steps()?);
+        }
+        Ok(layers)
+    }
+
+    fn term(&mut self) -> Result<Node, String> {
+        if self.eat('~') {
+            Ok(Node::Rest)
+        } else if self.eat('[') {
+            let layers = self.layers()?;
+            self.expect(']')?;
+            Ok(stack(layers.into_iter().map(cat).collect()))
+        } else if self.eat('<') {
+            let steps = self.steps()?;
+            self.expect('>')?;
+            if steps.is_empty() {
+                return Ok(Node::Rest);
+            }
+            Ok(Node::Alt(steps.into_iter().map(|(node, _)| node).collect()))
+        } else if self.eat('{') {
+            let layers = self.layers()?;
+            self.expect('}')?;
+            let per_cycle = if self.eat('%') {
+                self.number()?
+            } else {
+                layers[0].len() as f64
+            };
+            Ok(stack(
+                layers
+                    .into_iter()
+                    .filter(|layer| !layer.is_empty() && per_cycle > 0.0)
+                    .map(|layer| {
+                        let len = layer.len() as f64;
+                        Node::Fast(Box::new(cat(layer)), per_cycle / len)
+                    })
+                    .collect(),
+            ))
+        } else {
+            let start = self.pos;
+            while self
+                .peek()
+                .is_some_and(|c| c.is_alphanumeric() || "#.-_".contains(c))
+            {
+                self.pos += 1;
+            }
+            if start == self.pos {
+                return Err(match self.peek() {
+                    Some(c) => self.error(&format!("unexpected `{c}`")),
+                    None => self.error("unexpected end"),
+                });
+            }
+            let word = self.chars[start..self.pos].iter().collect::<String>();
+            (self.word)(&word)
+                .map(Node::Value)
+                .ok_or_else(|| self.error(&format!("unknown value `{word}`")))
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+            .filter(|n: &f64| *n > 0.0)
+            .ok_or_else(|| self.error("expected a positive number"))
+    }
+}
+
+fn cat(steps: Vec<(Node, f64)>) -> Node {
+    if steps.is_empty() {
+        Node::Rest
+    } else {
+        Node::Cat(steps)
+    }
+}
+
+fn stack(mut layers: Vec<Node>) -> Node {
+    match layers.len() {
+        0 => Node::Rest,
+        1 => layers.remove(0),
+        _ => Node::Stack(layers),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Onsets of `src` over `cycles` as (twelfths of a cycle, value).
+    fn onsets(src: &str, cycles: usize) -> Vec<(i64, f32)> {
+        let root = Parser::parse(src, gate).unwrap();
+        let mut onsets = Vec::new();
+        let mut last = None;
+        for i in 0..cycles * 120 {
+            let event = root.query(i as f64 / 120.0);
+            let begin = event.as_ref().map(|event| event.begin);
+            if let Some(event) = event
+                && begin != last
+            {
+                onsets.push(((event.begin * 12.0).round() as i64, event.value));
+            }
+            last = begin;
+        }
+        onsets
+    }
+
+    #[test]
+    fn steps() {
+        assert_eq!(onsets("x ~ 0.5 x", 1), [(0, 1.0), (6, 0.5), (9, 1.0)]);
+        assert_eq!(onsets("x [x x] ~", 1), [(0, 1.0), (4, 1.0), (6, 1.0)]);
+    }
+
+    #[test]
+    fn fast() {
+        assert_eq!(
+            onsets("x*2 ~", 2),
+            [(0, 1.0), (3, 1.0), (12, 1.0), (15, 1.0)]
+        );
+    }
+
+    #[test]
+    fn slow() {
+        assert_eq!(onsets("x/2 ~", 4), [(0, 1.0), (24, 1.0)]);
+        assert_eq!(onsets("x/2", 4), [(0, 1.0), (24, 1.0)]);
+    }
+
+    #[test]
+    fn alternate() {
+        assert_eq!(
+            onsets("<x 0.5> x", 2),
+            [(0, 1.0), (6, 1.0), (12, 0.5), (18, 1.0)]
+        );
+    }
+
+    #[test]
+    fn elongate() {
+        assert_eq!(onsets("x@3 0.5", 1), [(0, 1.0), (9, 0.5)]);
+    }
+
+    #[test]
+    fn replicate() {
+        assert_eq!(onsets("x!3 ~", 1), [(0, 1.0), (3, 1.0), (6, 1.0)]);
+        assert_eq!(onsets("x ! ~ !", 1), [(0, 1.0), (3, 1.0)]);
+    }
+
+    #[test]
+    fn polymeter() {
+        assert_eq!(
+            onsets("{x 0.5 0.25}%4", 2),
+            [
+                (0, 1.0),
+                (3, 0.5),
+                (6, 0.25),
+                (9, 1.0),
+                (12, 0.5),
+                (15, 0.25),
+                (18, 1.0),
+                (21, 0.5)
+            ]
+        );
+        assert_eq!(
+            onsets("{x ~ ~, 0.5 ~}", 2),
+            [(0, 1.0), (8, 0.5), (12, 1.0), (16, 0.5)]
+        );
+        assert_eq!(
+            onsets("[x x, 0.5 ~ 0.5]", 1),
+            [(0, 1.0), (6, 1.0), (8, 0.5)]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for src in ["x [", "x]", "x@", "x*0", "<x, y>", "!", "x $"] {
+            assert!(Parser::parse(src, gate).is_err(), "{src}");
+        }
+        assert!(Parser::parse("c4 q", note).is_err());
+        assert!(pat("x [").is_err());
+        assert!(super::notes("c2 eb2 <g2 f2>").is_ok());
+    }
+
+    #[test]
+    fn notes() {
+        let hz = |word| note(word).unwrap();
+        assert_eq!(hz("a4"), 440.0);
+        assert_eq!(hz("a"), 220.0);
+        assert_eq!(hz("69"), 440.0);
+        assert!((hz("c#4") - hz("db4")).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::delay::DelayLine;
+use crate::filter::OnePole;
+use crate::rng::{Rng, rand};
+use crate::{An, Config, F, IntoStereo, MonoSrcBound, Process};
+use std::marker::PhantomData;
+
+/// Stereo feedback delay network reverb.
+pub fn reverb<In>() -> An<Reverb<In, f32, f32, f32, f32, f32>>
+where
+    In: IntoStereo,
+{
+    An(Reverb {
+        fdn: Fdn::new(0),
+        room: 0.5,
+        damp: 0.5,
+        predelay: 0.01,
+        width: 1.0,
+        mix: 0.3,
+        _in: PhantomData,
+    })
+}
+
+pub struct Reverb<In, Room, Damp, Predelay, Width, Mix> {
+    fdn: Fdn,
+    room: Room,
+    damp: Damp,
+    predelay: Predelay,
+    width: Width,
+    mix: Mix,
+    _in: PhantomData<In>,
+}
+
+impl<In, Room, Damp, Predelay, Width, Mix> An<Reverb<In, Room, Damp, Predelay, Width, Mix>>
+where
+    In: IntoStereo,
+    Room: MonoSrcBound,
+    Damp: MonoSrcBound,
+    Predelay: MonoSrcBound,
+    Width: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.0.fdn.rng = rand(seed);
+        self
+    }
+
+    /// Room size from 0 to 1, mapped to a decay time of 0.2 to 10 seconds.
+    pub fn room<Room1>(self, room: Room1) -> An<Reverb<In, Room1, Damp, Predelay, Width, Mix>>
+    where
+        Room1: MonoSrcBound,
+    {
+        An(Reverb {
+            fdn: self.0.fdn,
+            room,
+            damp: self.0.damp,
+            predelay: self.0.predelay,
+            width: self.0.width,
+            mix: self.0.mix,
+            _in: PhantomData,
+        })
+    }
+
+    pub fn damp<Damp1>(self, damp: Damp1) -> An<Reverb<In, Room, Damp1, Predelay, Width, Mix>>
+    where
+        Damp1: MonoSrcBound,
+    {
+        An(Reverb {
+            fdn: self.0.fdn,
+            room: self.0.room,
+            damp,
+            predelay: self.0.predelay,
+            width: self.0.width,
+            mix: self.0.mix,
+            _in: PhantomData,
+        })
+    }
+
+    /// Seconds before the input reaches the network.
+    pub fn predelay<Predelay1>(
+        self,
+        predelay: Predelay1,
+    ) -> An<Reverb<In, Room, Damp, Predelay1, Width, Mix>>
+    where
+        Predelay1: MonoSrcBound,
+    {
+        An(Reverb {
+            fdn: self.0.fdn,
+            room: self.0.room,
+            damp: self.0.damp,
+            predelay,
+            width: self.0.width,
+            mix: self.0.mix,
+            _in: PhantomData,
+        })
+    }
+
+    pub fn width<Width1>(self, width: Width1) -> An<Reverb<In, Room, Damp, Predelay, Width1, Mix>>
+    where
+        Width1: MonoSrcBound,
+    {
+        An(Reverb {
+            fdn: self.0.fdn,
+            room: self.0.room,
+            damp: self.0.damp,
+            predelay: self.0.predelay,
+            width,
+            mix: self.0.mix,
+            _in: PhantomData,
+        })
+    }
+
+    pub fn mix<Mix1>(self, mix: Mix1) -> An<Reverb<In, Room, Damp, Predelay, Width, Mix1>>
+    where
+        Mix1: MonoSrcBound,
+    {
+        An(Reverb {
+            fdn: self.0.fdn,
+            room: self.0.room,
+            damp: self.0.damp,
+            predelay: self.0.predelay,
+            width: self.0.width,
+            mix,
+            _in: PhantomData,
+        })
+    }
+}
+
+impl<In, Room, Damp, Predelay, Width, Mix> Process for Reverb<In, Room, Damp, Predelay, Width, Mix>
+where
+    In: IntoStereo,
+    Room: MonoSrcBound,
+    Damp: MonoSrcBound,
+    Predelay: MonoSrcBound,
+    Width: MonoSrcBound,
+    Mix: MonoSrcBound,
+{
+    type Input = In;
+    type Output = F<2>;
+
+    fn reset(&mut self) {
+        self.fdn.reset();
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let room = self.room.filter_mono(config).clamp(0.0, 1.0) as f64;
+        let damp = self.damp.filter_mono(config).clamp(0.0, 1.0) as f64;
+        let predelay = self.predelay.filter_mono(config) as f64;
+        let width = self.width.filter_mono(config);
+        let mix = self.mix.filter_mono(config);
+
+        let input = input.into_stereo();
+        let wet = self.fdn.sample(config, input, room, damp, predelay);
+        let mid = (wet[0] + wet[1]) * 0.5;
+        let side = (wet[0] - wet[1]) * 0.5 * width;
+        F([
+            input[0] * (1.0 - mix) + (mid + side) * mix,
+            input[1] * (1.0 - mix) + (mid - side) * mix,
+        ])
+    }
+}
+
+const LINES: usize = 8;
+
+/// Line lengths in samples at 48kHz, chosen to be mutually prime.
+const LENGTHS: [f64; LINES] = [
+    1433.0, 1601.0, 1867.0, 2053.0, 2251.0, 2399.0, 2617.0, 2791.0,
+];
+
+/// Peak modulation of each line in samples at 48kHz.
+const MOD_DEPTH: f64 = 12.0;
+
+/// Samples between new modulation targets at 48kHz.
+const MOD_PERIOD: usize = 12_000;
+
+/// Implementation based on these resources:
+/// - https://ccrma.stanford.edu/~jos/pasp/Feedback_Delay_Networks_FDN.html
+/// - https://signalsmith-audio.co.uk/writing/2021/lets-write-a-reverb/
+struct Fdn {
+    predelay: [DelayLine; 2],
+    lines: [DelayLine; LINES],
+    lp: [OnePole; LINES],
+    rng: An<Rng>,
+    modulation: [(f64, f64); LINES],
+    tick: usize,
+}
+
+impl Fdn {
+    fn new(seed: u32) -> Self {
+        Self {
+            predelay: Default::default(),
+            lines: Default::default(),
+            lp: Default::default(),
+            rng: rand(seed),
+            modulation: [(0.0, 0.0); LINES],
+            tick: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        for line in self.predelay.iter_mut().chain(self.lines.iter_mut()) {
+            line.clear();
+        }
+        for lp in self.lp.iter_mut() {
+            lp.reset();
+        }
+        self.rng.reset();
+        self.modulation = [(0.0, 0.0); LINES];
+        self.tick = 0;
+    }
+
+    fn sample(
+        &mut self,
+        config: &Config,
+        input: F<2>,
+        room: f64,
+        damp: f64,
+        predelay: f64,
+    ) -> F<2> {
+        let scale = config.sample_rate / 48_000.0;
+        let rt60 = 0.2 * 50f64.powf(room);
+        let cutoff = 200.0 * 100f64.powf(1.0 - damp);
+        let coefficient = OnePole::coefficient(cutoff, config.sample_rate);
+
+        let period = (MOD_PERIOD as f64 * scale) as usize;
+        if self.tick.is_multiple_of(period.max(1)) {
+            for (_, target) in self.modulation.iter_mut() {
+                *target = (self.rng.sample(config, ())[0] as f64 * 2.0 - 1.0) * MOD_DEPTH * scale;
+            }
+        }
+        self.tick = self.tick.wrapping_add(1);
+        let glide = 1.0 / period as f64;
+
+        let predelay = predelay * config.sample_rate;
+        let mut pre = [0.0; 2];
+        for ((line, sample), input) in self.predelay.iter_mut().zip(pre.iter_mut()).zip(input.0) {
+            if predelay >= 1.0 {
+                line.reserve(predelay);
+                *sample = line.tap(predelay);
+                line.write(input);
+            } else {
+                *sample = input;
+            }
+        }
+
+        let mut taps = [0.0; LINES];
+        for (i, tap) in taps.iter_mut().enumerate() {
+            let (current, target) = &mut self.modulation[i];
+            *current += (*target - *current) * glide;
+            let delay = LENGTHS[i] * scale + *current;
+            self.lines[i].reserve(delay + MOD_DEPTH * scale);
+            *tap = self.lines[i].tap(delay);
+        }
+
+        // Householder reflection keeps the network lossless before damping.
+        let householder = taps.iter().sum::<f32>() * (2.0 / LINES as f32);
+        let mut output = F([0.0; 2]);
+        for (i, tap) in taps.iter().enumerate() {
+            let gain = 10f64.powf(-3.0 * LENGTHS[i] * scale / (rt60 * config.sample_rate)) as f32;
+            let feedback = self.lp[i].filter(tap - householder, coefficient) * gain;
+            self.lines[i].write(pre[i % 2] * 0.5 + feedback);
+            output[i % 2] += tap;
+        }
+        output[0] *= 2.0 / LINES as f32;
+        output[1] *= 2.0 / LINES as f32;
+        output
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{F, An, Process, fmono};
+use crate::{An, F, Process, fmono};
 
 pub struct Rng {
     seed: u32,
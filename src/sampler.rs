@@ -0,0 +1,364 @@
+use crate::note::Clock;
+use crate::transport::Dur;
+use crate::wav::Wav;
+use crate::{An, Config, F, MonoSrcBound, Process, math};
+use std::sync::Arc;
+
+/// Mono sample player, started on the rising edge of `.trig()` or when
+/// reset.
+pub fn sampler(wav: impl Into<Arc<Wav>>) -> An<Sampler<1, f32, f32>> {
+    An(Sampler::new(wav.into()))
+}
+
+pub fn sampler_stereo(wav: impl Into<Arc<Wav>>) -> An<Sampler<2, f32, f32>> {
+    An(Sampler::new(wav.into()))
+}
+
+pub struct Sampler<const N: usize, Trig, Rate> {
+    wav: Arc<Wav>,
+    trig: Trig,
+    rate: Rate,
+    start: f32,
+    end: f32,
+    looping: Option<(f32, f32)>,
+    reverse: bool,
+    sensitivity: f32,
+    triggered: bool,
+    position: Option<f64>,
+    velocity: f32,
+}
+
+impl<const N: usize> Sampler<N, f32, f32> {
+    fn new(wav: Arc<Wav>) -> Self {
+        Self {
+            wav,
+            trig: 0.0,
+            rate: 1.0,
+            start: 0.0,
+            end: 1.0,
+            looping: None,
+            reverse: false,
+            sensitivity: 1.0,
+            triggered: false,
+            position: None,
+            velocity: 1.0,
+        }
+    }
+}
+
+impl<const N: usize, Trig, Rate> An<Sampler<N, Trig, Rate>>
+where
+    Trig: MonoSrcBound,
+    Rate: MonoSrcBound,
+{
+    pub fn trig<Trig1>(self, trig: Trig1) -> An<Sampler<N, Trig1, Rate>>
+    where
+        Trig1: MonoSrcBound,
+    {
+        An(Sampler {
+            wav: self.0.wav,
+            trig,
+            rate: self.0.rate,
+            start: self.0.start,
+            end: self.0.end,
+            looping: self.0.looping,
+            reverse: self.0.reverse,
+            sensitivity: self.0.sensitivity,
+            triggered: self.0.triggered,
+            position: self.0.position,
+            velocity: self.0.velocity,
+        })
+    }
+
+    /// Playback speed, where 2 is an octave up.
+    pub fn rate<Rate1>(self, rate: Rate1) -> An<Sampler<N, Trig, Rate1>>
+    where
+        Rate1: MonoSrcBound,
+    {
+        An(Sampler {
+            wav: self.0.wav,
+            trig: self.0.trig,
+            rate,
+            start: self.0.start,
+            end: self.0.end,
+            looping: self.0.looping,
+            reverse: self.0.reverse,
+            sensitivity: self.0.sensitivity,
+            triggered: self.0.triggered,
+            position: self.0.position,
+            velocity: self.0.velocity,
+        })
+    }
+
+    pub fn start(mut self, start: f32) -> Self {
+        self.0.start = start.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn end(mut self, end: f32) -> Self {
+        self.0.end = end.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Repeat the part between `start` and `end` once playback reaches it.
+    pub fn looping(mut self, start: f32, end: f32) -> Self {
+        assert!(start < end, "loop start must be before loop end");
+        self.0.looping = Some((start.clamp(0.0, 1.0), end.clamp(0.0, 1.0)));
+        self
+    }
+
+    pub fn reverse(mut self) -> Self {
+        self.0.reverse = true;
+        self
+    }
+
+    /// How much the trigger level scales the output, from 0 to 1.
+    pub fn velocity(mut self, sensitivity: f32) -> Self {
+        self.0.sensitivity = sensitivity.clamp(0.0, 1.0);
+        self
+    }
+}
+
+impl<const N: usize, Trig, Rate> Sampler<N, Trig, Rate> {
+    fn play(&mut self, velocity: f32) {
+        let len = self.wav.len() as f64;
+        self.position = Some(if self.reverse {
+            self.end as f64 * len - 1.0
+        } else {
+            self.start as f64 * len
+        });
+        self.velocity = 1.0 - self.sensitivity + self.sensitivity * velocity;
+    }
+
+    fn frame(&self, position: f64) -> F<N> {
+        let index = position.floor() as isize;
+        let t = (position - index as f64) as f32;
+        let at = |channel: &[f32], i: isize| {
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| channel.get(i))
+                .copied()
+                .unwrap_or(0.0)
+        };
+        let read = |channel: &[f32]| {
+            math::hermite(
+                t,
+                at(channel, index - 1),
+                at(channel, index),
+                at(channel, index + 1),
+                at(channel, index + 2),
+            )
+        };
+
+        let channels = &self.wav.channels;
+        let mut frame = F([0.0; N]);
+        if N == 1 {
+            frame[0] = channels.iter().map(|c| read(c)).sum::<f32>() / channels.len() as f32;
+        } else {
+            for (i, sample) in frame.iter_mut().enumerate() {
+                *sample = read(&channels[i % channels.len()]);
+            }
+        }
+        frame
+    }
+}
+
+impl<const N: usize, Trig, Rate> Process for Sampler<N, Trig, Rate>
+where
+    Trig: MonoSrcBound,
+    Rate: MonoSrcBound,
+{
+    type Input = ();
+    type Output = F<N>;
+
+    fn reset(&mut self) {
+        self.play(1.0);
+    }
+
+    fn sample(&mut self, config: &Config, _: Self::Input) -> Self::Output {
+        let trigger = self.trig.filter_mono(config);
+        let rate = self.rate.filter_mono(config) as f64;
+        if !self.triggered && trigger > 0.0 {
+            self.triggered = true;
+            self.play(trigger);
+        } else if self.triggered && trigger <= 0.0 {
+            self.triggered = false;
+        }
+
+        let Some(position) = self.position else {
+            return F([0.0; N]);
+        };
+        let len = self.wav.len() as f64;
+        let mut output = self.frame(position);
+        for sample in output.iter_mut() {
+            *sample *= self.velocity;
+        }
+
+        let step = rate * self.wav.sample_rate as f64 * config.sample_duration;
+        let mut next = if self.reverse {
+            position - step
+        } else {
+            position + step
+        };
+        if let Some((start, end)) = self.looping {
+            let (start, end) = (start as f64 * len, end as f64 * len);
+            if !self.reverse && next >= end && position < end {
+                next -= end - start;
+            } else if self.reverse && next < start && position >= start {
+                next += end - start;
+            }
+        }
+        let (start, end) = (self.start as f64 * len, self.end as f64 * len);
+        self.position = (next >= start && next < end).then_some(next);
+        output
+    }
+}
+
+/// Mono beat slicer that plays the slice chosen by `.index()` whenever
+/// it changes.
+pub fn slicer(wav: impl Into<Arc<Wav>>) -> An<Slicer<1, f32>> {
+    An(Slicer::new(wav.into()))
+}
+
+pub fn slicer_stereo(wav: impl Into<Arc<Wav>>) -> An<Slicer<2, f32>> {
+    An(Slicer::new(wav.into()))
+}
+
+pub struct Slicer<const N: usize, Index> {
+    player: Sampler<N, f32, f32>,
+    slices: Vec<usize>,
+    index: Index,
+    clock: Option<Clock>,
+    beats: Option<f64>,
+    current: Option<usize>,
+}
+
+impl<const N: usize> Slicer<N, f32> {
+    fn new(wav: Arc<Wav>) -> Self {
+        let mut slicer = Self {
+            player: Sampler::new(wav),
+            slices: Vec::new(),
+            index: 0.0,
+            clock: None,
+            beats: None,
+            current: None,
+        };
+        slicer.slices = equal(slicer.player.wav.len(), 8);
+        slicer
+    }
+}
+
+fn equal(len: usize, slices: usize) -> Vec<usize> {
+    (0..slices.max(1))
+        .map(|i| i * len / slices.max(1))
+        .collect()
+}
+
+/// Onsets where the energy of 10 ms rises above `threshold` times the energy
+/// of the 40 ms before.
+fn transients(wav: &Wav, threshold: f32) -> Vec<usize> {
+    let hop = (wav.sample_rate as usize / 100).max(1);
+    let energy = (0..wav.len())
+        .step_by(hop)
+        .map(|start| {
+            (start..(start + hop).min(wav.len()))
+                .map(|i| {
+                    let sample = wav.channels.iter().map(|c| c[i]).sum::<f32>();
+                    sample * sample
+                })
+                .sum::<f32>()
+        })
+        .collect::<Vec<_>>();
+    let floor = energy.iter().copied().fold(0.0, f32::max) * 0.001;
+
+    let mut onsets = vec![0];
+    for i in 4..energy.len() {
+        let before = energy[i - 4..i].iter().sum::<f32>() / 4.0;
+        let last = onsets[onsets.len() - 1];
+        // at least 50 ms between onsets
+        if energy[i] > floor && energy[i] > before * threshold && i * hop >= last + hop * 5 {
+            onsets.push(i * hop);
+        }
+    }
+    onsets
+}
+
+impl<const N: usize, Index> An<Slicer<N, Index>>
+where
+    Index: MonoSrcBound,
+{
+    pub fn slices(mut self, slices: usize) -> Self {
+        self.0.slices = equal(self.0.player.wav.len(), slices);
+        self
+    }
+
+    pub fn transients(mut self, threshold: f32) -> Self {
+        self.0.slices = transients(&self.0.player.wav, threshold);
+        self
+    }
+
+    pub fn index<Index1>(self, index: Index1) -> An<Slicer<N, Index1>>
+    where
+        Index1: MonoSrcBound,
+    {
+        An(Slicer {
+            player: self.0.player,
+            slices: self.0.slices,
+            index,
+            clock: self.0.clock,
+            beats: self.0.beats,
+            current: self.0.current,
+        })
+    }
+
+    /// Retrigger the current slice every step, even if the index didn't change.
+    pub fn steps(mut self, steps: impl Into<Dur>) -> Self {
+        self.0.clock = Some(Clock::new(steps.into()));
+        self
+    }
+
+    /// Length of the whole loop in beats, setting a rate that follows the tempo.
+    pub fn beats(mut self, beats: f64) -> Self {
+        self.0.beats = Some(beats);
+        self
+    }
+}
+
+impl<const N: usize, Index> Process for Slicer<N, Index>
+where
+    Index: MonoSrcBound,
+{
+    type Input = ();
+    type Output = F<N>;
+
+    fn reset(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            clock.reset();
+        }
+        self.current = None;
+        self.player.position = None;
+    }
+
+    fn sample(&mut self, config: &Config, _: Self::Input) -> Self::Output {
+        let index = self.index.filter_mono(config);
+        let slice = (index.round() as i64).rem_euclid(self.slices.len() as i64) as usize;
+        let step = match &mut self.clock {
+            Some(clock) => clock.tick(config),
+            None => self.current != Some(slice),
+        };
+
+        if let Some(beats) = self.beats {
+            let seconds = self.player.wav.len() as f64 / self.player.wav.sample_rate as f64;
+            self.player.rate = (seconds / (beats * config.spb * config.sample_duration)) as f32;
+        }
+        if step {
+            let len = self.player.wav.len() as f32;
+            let end = self.slices.get(slice + 1).copied();
+            self.player.start = self.slices[slice] as f32 / len;
+            self.player.end = end.map_or(1.0, |end| end as f32 / len);
+            self.player.play(1.0);
+            self.current = Some(slice);
+        }
+        self.player.sample(config, ())
+    }
+}
@@ -0,0 +1,116 @@
+use crate::delay::DelayLine;
+use crate::{An, Config, F, IntoStereo, MonoSrcBound, Process, math};
+use std::marker::PhantomData;
+
+pub fn pan<Position>(position: Position) -> An<Pan<Position>>
+where
+    Position: MonoSrcBound,
+{
+    An(Pan(position))
+}
+
+pub struct Pan<Position>(Position);
+
+impl<Position> Process for Pan<Position>
+where
+    Position: MonoSrcBound,
+{
+    type Input = F<1>;
+    type Output = F<2>;
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let (left, right) = math::pan(self.0.filter_mono(config));
+        F([input[0] * left, input[0] * right])
+    }
+}
+
+/// Balance that attenuates the side being moved away from.
+pub fn balance<Position>(position: Position) -> An<Balance<Position>>
+where
+    Position: MonoSrcBound,
+{
+    An(Balance(position))
+}
+
+pub struct Balance<Position>(Position);
+
+impl<Position> Process for Balance<Position>
+where
+    Position: MonoSrcBound,
+{
+    type Input = F<2>;
+    type Output = F<2>;
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let (left, right) = math::pan(self.0.filter_mono(config));
+        let left = (left * core::f32::consts::SQRT_2).min(1.0);
+        let right = (right * core::f32::consts::SQRT_2).min(1.0);
+        F([input[0] * left, input[1] * right])
+    }
+}
+
+/// Mid/side width, where 0 is mono and 1 leaves the input untouched.
+pub fn width<Width>(width: Width) -> An<StereoWidth<Width>>
+where
+    Width: MonoSrcBound,
+{
+    An(StereoWidth(width))
+}
+
+pub struct StereoWidth<Width>(Width);
+
+impl<Width> Process for StereoWidth<Width>
+where
+    Width: MonoSrcBound,
+{
+    type Input = F<2>;
+    type Output = F<2>;
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let width = self.0.filter_mono(config).max(0.0);
+        let mid = (input[0] + input[1]) * 0.5;
+        let side = (input[0] - input[1]) * 0.5 * width;
+        F([mid + side, mid - side])
+    }
+}
+
+/// Haas widener that delays the right channel by `seconds`.
+pub fn haas<In, Time>(seconds: Time) -> An<Haas<In, Time>>
+where
+    In: IntoStereo,
+    Time: MonoSrcBound,
+{
+    An(Haas {
+        line: DelayLine::default(),
+        time: seconds,
+        _in: PhantomData,
+    })
+}
+
+pub struct Haas<In, Time> {
+    line: DelayLine,
+    time: Time,
+    _in: PhantomData<In>,
+}
+
+impl<In, Time> Process for Haas<In, Time>
+where
+    In: IntoStereo,
+    Time: MonoSrcBound,
+{
+    type Input = In;
+    type Output = F<2>;
+
+    fn reset(&mut self) {
+        self.line.clear();
+    }
+
+    fn sample(&mut self, config: &Config, input: Self::Input) -> Self::Output {
+        let delay = self.time.filter_mono(config) as f64 * config.sample_rate;
+        let input = input.into_stereo();
+        self.line.reserve(delay);
+        let right = self.line.tap(delay);
+        self.line.write(input[1]);
+        F([input[0], right])
+    }
+}
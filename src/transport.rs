@@ -0,0 +1,288 @@
+use std::time::Instant;
+
+pub const TICKS_PER_BEAT: u32 = 96;
+
+/// Beats are summed per sample, so positions allow for rounding just below a
+/// step or bar line.
+const EPSILON: f64 = 1e-9;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TimeSignature {
+    pub beats: u32,
+    pub unit: u32,
+}
+
+impl TimeSignature {
+    pub fn new(beats: u32, unit: u32) -> Self {
+        assert!(
+            beats > 0 && unit.is_power_of_two(),
+            "invalid time signature {beats}/{unit}"
+        );
+        Self { beats, unit }
+    }
+
+    /// Length of a bar in quarter note beats.
+    pub fn beats_per_bar(self) -> f64 {
+        self.beats as f64 * 4.0 / self.unit as f64
+    }
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self::new(4, 4)
+    }
+}
+
+/// Song position shared by every process, in quarter note beats.
+#[derive(Clone, Copy, Default)]
+pub struct Transport {
+    pub samples: u64,
+    pub beats: f64,
+    pub signature: TimeSignature,
+    pub section: &'static str,
+    bars: u64,
+    bar_start: f64,
+    next_signature: Option<TimeSignature>,
+}
+
+impl Transport {
+    pub fn new(signature: TimeSignature) -> Self {
+        Self {
+            signature,
+            ..Default::default()
+        }
+    }
+
+    pub fn advance(&mut self, spb: f64) {
+        self.samples += 1;
+        self.beats += 1.0 / spb;
+        if self.beats - self.bar_start + EPSILON >= self.signature.beats_per_bar() {
+            self.bars += 1;
+            self.bar_start += self.signature.beats_per_bar();
+            if let Some(signature) = self.next_signature.take() {
+                self.signature = signature;
+            }
+        }
+    }
+
+    /// Change the time signature at the start of the next bar.
+    pub fn set_signature(&mut self, signature: TimeSignature) {
+        if self.samples == 0 {
+            self.signature = signature;
+        } else {
+            self.next_signature = Some(signature);
+        }
+    }
+
+    pub fn bar(&self) -> u64 {
+        self.bars
+    }
+
+    pub fn beat(&self) -> u32 {
+        (self.beats - self.bar_start + EPSILON).floor() as u32
+    }
+
+    pub fn tick(&self) -> u32 {
+        (self.phase() * TICKS_PER_BEAT as f64) as u32
+    }
+
+    pub fn phase(&self) -> f64 {
+        self.beats.fract()
+    }
+
+    pub fn bar_phase(&self) -> f64 {
+        ((self.beats - self.bar_start) / self.signature.beats_per_bar()).clamp(0.0, 1.0)
+    }
+
+    pub fn bars(&self) -> f64 {
+        self.bars as f64 + self.bar_phase()
+    }
+
+    /// Steps of `dur` since the start.
+    pub fn position(&self, dur: Dur) -> f64 {
+        dur.steps(self) + EPSILON
+    }
+}
+
+/// Live tempo of the `acid!` set, read once per sample into `Config::bpm`.
+#[derive(Clone)]
+pub struct Tempo {
+    bpm: f64,
+    declared: f64,
+    ramp: Option<Ramp>,
+    next_bar: Option<f64>,
+    bar: u64,
+    taps: Vec<Instant>,
+}
+
+#[derive(Clone, Copy)]
+struct Ramp {
+    from: f64,
+    to: f64,
+    beats: f64,
+    start: Option<f64>,
+}
+
+impl Tempo {
+    const TAPS: usize = 4;
+
+    pub fn new(bpm: f64) -> Self {
+        assert!(bpm > 0.0, "invalid tempo {bpm}");
+        Self {
+            bpm,
+            declared: bpm,
+            ramp: None,
+            next_bar: None,
+            bar: 0,
+            taps: Vec::new(),
+        }
+    }
+
+    /// Keep the live tempo unless the declared tempo changed.
+    pub fn retain(&mut self, old: &Tempo) {
+        if self.declared == old.declared {
+            self.clone_from(old);
+        }
+    }
+
+    pub fn bpm(&self) -> f64 {
+        self.bpm
+    }
+
+    pub fn set(&mut self, bpm: f64) {
+        assert!(bpm > 0.0, "invalid tempo {bpm}");
+        self.bpm = bpm;
+        self.ramp = None;
+        self.next_bar = None;
+    }
+
+    pub fn ramp(&mut self, bpm: f64, beats: f64) {
+        assert!(bpm > 0.0, "invalid tempo {bpm}");
+        self.ramp = Some(Ramp {
+            from: self.bpm,
+            to: bpm,
+            beats,
+            start: None,
+        });
+        self.next_bar = None;
+    }
+
+    pub fn at_next_bar(&mut self, bpm: f64) {
+        assert!(bpm > 0.0, "invalid tempo {bpm}");
+        self.next_bar = Some(bpm);
+    }
+
+    /// Set the tempo from the average time between the last few taps. A pause of
+    /// more than two seconds starts over.
+    pub fn tap(&mut self, at: Instant) {
+        if let Some(last) = self.taps.last()
+            && at.duration_since(*last).as_secs_f64() > 2.0
+        {
+            self.taps.clear();
+        }
+        if self.taps.len() == Self::TAPS {
+            self.taps.remove(0);
+        }
+        self.taps.push(at);
+        if let [first, .., last] = self.taps[..] {
+            let seconds = last.duration_since(first).as_secs_f64() / (self.taps.len() - 1) as f64;
+            if seconds > 0.0 {
+                self.set(60.0 / seconds);
+            }
+        }
+    }
+
+    pub fn update(&mut self, transport: &Transport) -> f64 {
+        if transport.bar() != self.bar {
+            self.bar = transport.bar();
+            if let Some(bpm) = self.next_bar.take() {
+                self.set(bpm);
+            }
+        }
+        if let Some(ramp) = &mut self.ramp {
+            let start = *ramp.start.get_or_insert(transport.beats);
+            let t = if ramp.beats > 0.0 {
+                ((transport.beats - start) / ramp.beats).min(1.0)
+            } else {
+                1.0
+            };
+            self.bpm = ramp.from + (ramp.to - ramp.from) * t;
+            if t >= 1.0 {
+                self.ramp = None;
+            }
+        }
+        self.bpm
+    }
+}
+
+/// Musical duration, where integers are steps per beat.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Dur {
+    beats: f64,
+    bars: f64,
+}
+
+impl Dur {
+    pub fn beats(beats: f64) -> Self {
+        Self { beats, bars: 0.0 }
+    }
+
+    /// A fraction of a whole note, e.g. `Dur::note(1, 16)` for a sixteenth.
+    pub fn note(numerator: u32, denominator: u32) -> Self {
+        Self::beats(4.0 * numerator as f64 / denominator as f64)
+    }
+
+    pub fn bars(bars: f64) -> Self {
+        Self { beats: 0.0, bars }
+    }
+
+    pub fn triplet(self) -> Self {
+        self * (2.0 / 3.0)
+    }
+
+    pub fn dotted(self) -> Self {
+        self * 1.5
+    }
+
+    pub fn to_beats(self, signature: TimeSignature) -> f64 {
+        self.beats + self.bars * signature.beats_per_bar()
+    }
+
+    /// Steps since the start of `transport`, where durations in bars count bars.
+    pub fn steps(self, transport: &Transport) -> f64 {
+        if self.beats == 0.0 {
+            transport.bars() / self.bars
+        } else {
+            transport.beats / self.to_beats(transport.signature)
+        }
+    }
+}
+
+impl core::ops::Mul<f64> for Dur {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            beats: self.beats * rhs,
+            bars: self.bars * rhs,
+        }
+    }
+}
+
+impl core::ops::Div<f64> for Dur {
+    type Output = Self;
+    fn div(self, rhs: f64) -> Self::Output {
+        self * (1.0 / rhs)
+    }
+}
+
+impl From<usize> for Dur {
+    fn from(steps: usize) -> Self {
+        Self::beats(1.0 / steps as f64)
+    }
+}
+
+impl From<i32> for Dur {
+    fn from(steps: i32) -> Self {
+        Self::beats(1.0 / steps as f64)
+    }
+}
@@ -0,0 +1,198 @@
+use crate::groove::Groove;
+use crate::note::Clock;
+use crate::rng::{Rng, rand};
+use crate::transport::Dur;
+use crate::{An, Config, F, Process, fmono};
+
+/// Step sequencer of [`Trig`]s in sixteenths, with probabilities drawn from a
+/// seeded [`rand`].
+pub fn trigs(steps: impl IntoIterator<Item = impl Into<Trig>>) -> An<Trigs> {
+    let steps = steps.into_iter().map(Into::into).collect::<Vec<_>>();
+    assert!(!steps.is_empty(), "`trigs` needs at least one step");
+    An(Trigs {
+        steps,
+        clock: Clock::new(Dur::from(4)),
+        rng: rand(0),
+        origin: None,
+        pre: false,
+        fired: None,
+    })
+}
+
+/// Condition of a [`Trig`], after Elektron's trig conditions.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Cond {
+    Always,
+    /// Fires on the `a`th of every `b` cycles, counting from 1.
+    Cycle(u32, u32),
+    NotCycle(u32, u32),
+    First,
+    NotFirst,
+    /// Fires if the last trig with a condition or probability fired.
+    Pre,
+    NotPre,
+}
+
+/// Numbers convert to a trig at that velocity, where 0 is a rest.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Trig {
+    velocity: f32,
+    probability: f32,
+    ratchet: u32,
+    cond: Cond,
+}
+
+impl Trig {
+    pub const REST: Self = Self::hit(0.0);
+
+    pub const fn hit(velocity: f32) -> Self {
+        Self {
+            velocity,
+            probability: 1.0,
+            ratchet: 1,
+            cond: Cond::Always,
+        }
+    }
+
+    pub fn prob(mut self, probability: f32) -> Self {
+        self.probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn ratchet(mut self, count: u32) -> Self {
+        assert!(
+            (1..=4).contains(&count),
+            "a ratchet has between 1 and 4 hits"
+        );
+        self.ratchet = count;
+        self
+    }
+
+    pub fn when(mut self, cond: Cond) -> Self {
+        if let Cond::Cycle(a, b) | Cond::NotCycle(a, b) = cond {
+            assert!((1..=b).contains(&a), "invalid trig condition {a}:{b}");
+        }
+        self.cond = cond;
+        self
+    }
+
+    fn conditional(&self) -> bool {
+        self.probability < 1.0 || !matches!(self.cond, Cond::Always | Cond::Pre | Cond::NotPre)
+    }
+}
+
+impl From<f32> for Trig {
+    fn from(velocity: f32) -> Self {
+        Self::hit(velocity)
+    }
+}
+
+impl From<f64> for Trig {
+    fn from(velocity: f64) -> Self {
+        Self::hit(velocity as f32)
+    }
+}
+
+impl From<i32> for Trig {
+    fn from(velocity: i32) -> Self {
+        Self::hit(velocity as f32)
+    }
+}
+
+pub struct Trigs {
+    steps: Vec<Trig>,
+    clock: Clock,
+    rng: An<Rng>,
+    origin: Option<i64>,
+    pre: bool,
+    fired: Option<Trig>,
+}
+
+impl An<Trigs> {
+    pub fn steps(mut self, steps: impl Into<Dur>) -> Self {
+        let groove = self.0.clock.groove;
+        self.0.clock = Clock::new(steps.into());
+        self.0.clock.groove = groove;
+        self
+    }
+
+    pub fn swing(self, percent: f32) -> Self {
+        self.groove(Groove::swing(percent))
+    }
+
+    pub fn groove(mut self, groove: Groove) -> Self {
+        self.0.clock.groove = groove;
+        self
+    }
+
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.0.rng = rand(seed);
+        self
+    }
+}
+
+impl Trigs {
+    fn fires(&mut self, config: &Config, trig: Trig, cycle: i64) -> bool {
+        let (a, b) = match trig.cond {
+            Cond::Cycle(a, b) | Cond::NotCycle(a, b) => (a as i64, b as i64),
+            _ => (1, 1),
+        };
+        let fires = match trig.cond {
+            Cond::Always => true,
+            Cond::Cycle(..) => cycle % b == a - 1,
+            Cond::NotCycle(..) => cycle % b != a - 1,
+            Cond::First => cycle == 0,
+            Cond::NotFirst => cycle != 0,
+            Cond::Pre => self.pre,
+            Cond::NotPre => !self.pre,
+        };
+        // draw for every probability so the sequence doesn't depend on the
+        // conditions
+        let fires = if trig.probability < 1.0 {
+            self.rng.sample(config, ())[0] < trig.probability && fires
+        } else {
+            fires
+        };
+        if trig.conditional() {
+            self.pre = fires;
+        }
+        fires && trig.velocity > 0.0
+    }
+}
+
+impl Process for Trigs {
+    type Input = ();
+    type Output = F<1>;
+
+    fn reset(&mut self) {
+        self.clock.reset();
+        self.rng.reset();
+        self.origin = None;
+        self.pre = false;
+        self.fired = None;
+    }
+
+    fn sample(&mut self, config: &Config, _: Self::Input) -> Self::Output {
+        let len = self.steps.len() as i64;
+        if self.clock.tick(config) {
+            let step = self.clock.index();
+            let origin = *self.origin.get_or_insert(step.div_euclid(len));
+            let trig = self.steps[step.rem_euclid(len) as usize];
+            let cycle = step.div_euclid(len) - origin;
+            let velocity = trig.velocity * self.clock.groove.velocity(self.clock.step());
+            self.fired = self
+                .fires(config, trig, cycle)
+                .then_some(Trig { velocity, ..trig });
+        }
+        let Some(trig) = self.fired else {
+            return fmono(0.0);
+        };
+
+        // high until the last sample of each ratchet
+        let ratchet = trig.ratchet as f64;
+        let phase = self.clock.phase(config).clamp(0.0, 1.0);
+        let next = phase + 1.0 / (self.clock.beats(config) * config.spb);
+        let open = next < 1.0 && (next * ratchet).floor() == (phase * ratchet).floor();
+        fmono(if open { trig.velocity } else { 0.0 })
+    }
+}
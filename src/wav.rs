@@ -0,0 +1,162 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+#[derive(Clone)]
+pub struct Wav {
+    pub sample_rate: u32,
+    pub channels: Vec<Vec<f32>>,
+}
+
+/// Supports 8, 16, 24 and 32 bit integer and 32 and 64 bit float PCM.
+pub fn load(path: impl AsRef<Path>) -> Result<Wav> {
+    Wav::parse(&std::fs::read(path)?)
+}
+
+const PCM: u16 = 1;
+const FLOAT: u16 = 3;
+const EXTENSIBLE: u16 = 0xfffe;
+
+impl Wav {
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(invalid("missing RIFF/WAVE header"));
+        }
+
+        let mut format = None;
+        let mut data = None;
+        let mut chunks = &bytes[12..];
+        while chunks.len() >= 8 {
+            let id = &chunks[0..4];
+            let len = u32_le(&chunks[4..8]) as usize;
+            let body = chunks
+                .get(8..8 + len)
+                .ok_or_else(|| invalid("truncated chunk"))?;
+            match id {
+                b"fmt " => format = Some(Format::parse(body)?),
+                b"data" => data = Some(body),
+                _ => {}
+            }
+            // chunks are padded to an even length
+            chunks = chunks.get(8 + len + (len & 1)..).unwrap_or(&[]);
+        }
+
+        let format = format.ok_or_else(|| invalid("missing fmt chunk"))?;
+        let data = data.ok_or_else(|| invalid("missing data chunk"))?;
+        let width = format.bits as usize / 8;
+        let frame = width * format.channels;
+        let decode: fn(&[u8]) -> f32 = match (format.tag, format.bits) {
+            (PCM, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+            (PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32_768.0,
+            (PCM, 24) => |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0,
+            (PCM, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0,
+            (FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            (FLOAT, 64) => {
+                |b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32
+            }
+            (tag, bits) => {
+                return Err(invalid(&format!(
+                    "unsupported format {tag} with {bits} bits per sample"
+                )));
+            }
+        };
+
+        let mut channels = vec![Vec::with_capacity(data.len() / frame); format.channels];
+        for frame in data.chunks_exact(frame) {
+            for (channel, sample) in channels.iter_mut().zip(frame.chunks_exact(width)) {
+                channel.push(decode(sample));
+            }
+        }
+
+        Ok(Self {
+            sample_rate: format.sample_rate,
+            channels,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.channels.first().map(Vec::len).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn resample(&self, sample_rate: u32) -> Self {
+        if sample_rate == self.sample_rate || self.is_empty() {
+            return Self {
+                sample_rate,
+                channels: self.channels.clone(),
+            };
+        }
+
+        let ratio = self.sample_rate as f64 / sample_rate as f64;
+        let len = (self.len() as f64 / ratio) as usize;
+        let channels = self
+            .channels
+            .iter()
+            .map(|channel| {
+                (0..len)
+                    .map(|i| {
+                        let position = i as f64 * ratio;
+                        let index = position as usize;
+                        let t = (position - index as f64) as f32;
+                        let x0 = channel[index];
+                        let x1 = channel.get(index + 1).copied().unwrap_or(0.0);
+                        x0 + (x1 - x0) * t
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            sample_rate,
+            channels,
+        }
+    }
+}
+
+struct Format {
+    tag: u16,
+    channels: usize,
+    sample_rate: u32,
+    bits: u16,
+}
+
+impl Format {
+    fn parse(body: &[u8]) -> Result<Self> {
+        if body.len() < 16 {
+            return Err(invalid("truncated fmt chunk"));
+        }
+        let mut tag = u16_le(&body[0..2]);
+        if tag == EXTENSIBLE {
+            // the first two bytes of the sub-format GUID hold the real tag
+            tag = u16_le(
+                body.get(24..26)
+                    .ok_or_else(|| invalid("truncated fmt chunk"))?,
+            );
+        }
+        let channels = u16_le(&body[2..4]) as usize;
+        let bits = u16_le(&body[14..16]);
+        if channels == 0 || bits == 0 || !bits.is_multiple_of(8) {
+            return Err(invalid("invalid fmt chunk"));
+        }
+        Ok(Self {
+            tag,
+            channels,
+            sample_rate: u32_le(&body[4..8]),
+            bits,
+        })
+    }
+}
+
+fn u16_le(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn u32_le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("invalid WAV: {msg}"))
+}